@@ -9,6 +9,16 @@ pub struct Tile {
     pub y: u32,
     pub id: u32,
     pub tex_id: usize,
+    //the atlas layer the tile was allocated on.
+    pub layer: usize,
+}
+
+impl Tile {
+    //the Empty tile is the only tile at id 0 of the first atlas layer.
+    //id 0 of the other layers is a real tile.
+    pub fn is_empty(&self) -> bool {
+        self.id == 0 && self.layer == 0
+    }
 }
 
 #[derive(Debug, Default)]
@@ -89,6 +99,7 @@ impl TileSheet {
                     y: tiley,
                     id: 0,
                     tex_id: empty,
+                    layer: 0,
                 })
             } else {
                 let (posx, posy) = allocation.position();
@@ -97,6 +108,7 @@ impl TileSheet {
                     y: tiley,
                     id: (posx / tilesize) + ((posy / tilesize) * atlas_width),
                     tex_id,
+                    layer: allocation.layer,
                 })
             }
         }
@@ -176,6 +188,7 @@ impl TileSheet {
                     y: tiley,
                     id: 0,
                     tex_id: empty,
+                    layer: 0,
                 })
             } else {
                 let (posx, posy) = allocation.position();
@@ -184,6 +197,7 @@ impl TileSheet {
                     y: tiley,
                     id: (posx / tilesize) + ((posy / tilesize) * atlas_width),
                     tex_id,
+                    layer: allocation.layer,
                 })
             }
         }
@@ -0,0 +1,299 @@
+use std::{collections::BTreeMap, env, fs, process};
+use graphics::AscendingError;
+use log::{Level, LevelFilter, Metadata, Record};
+use mapeditor::map_data::*;
+
+static TOOL_LOGGER: ToolLogger = ToolLogger(Level::Info);
+
+struct ToolLogger(pub Level);
+
+impl log::Log for ToolLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.0
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{} - {}", record.level(), record.args());
+        }
+    }
+    fn flush(&self) {}
+}
+
+const USAGE: &str = "Usage: map_tool <command>
+    validate                                    Check that every map on ./data/maps can be loaded
+    convert <json|binary>                       Convert every map and set the format as the project format
+    move <x> <y> <group> <new x> <new y> <new group>   Move a map to another location
+    stats                                       Print statistics of every map
+    export <x> <y> <group> <file.tmj|file.tmx> [--no-neighbours]   Export a map and its neighbours to Tiled
+    import <file.tmj> <x> <y> <group> [--overwrite]     Slice a Tiled map into maps starting from the top left map
+    render <x> <y> <group> <file.png> [--to <x> <y>] [--grid] [--attributes]   Render a map or a rectangle of maps to PNG
+    cleanup [--dry-run]                         Remove the maps without content, the removed maps are kept as .bak1";
+
+fn main() {
+    log::set_logger(&TOOL_LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Info);
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("validate") => validate(),
+        Some("convert") => convert(&args[1..]),
+        Some("move") => move_map(&args[1..]),
+        Some("stats") => stats(),
+        Some("export") => export(&args[1..]),
+        Some("import") => import(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("cleanup") => cleanup(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {},
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn init_project() -> Result<(), AscendingError> {
+    fs::create_dir_all(MAP_PATH)?;
+    load_project_config()
+}
+
+fn parse_location(args: &[String]) -> Option<(i32, i32, u64)> {
+    match args {
+        [x, y, group] => Some((x.parse().ok()?, y.parse().ok()?, group.parse().ok()?)),
+        _ => None,
+    }
+}
+
+// Returns false when a map has a problem
+fn validate() -> Result<bool, AscendingError> {
+    init_project()?;
+    let mut is_valid = true;
+    let mut locations = BTreeMap::new();
+
+    for (name, format) in list_map_files()? {
+        let (data, version) = match read_map_file(&name, format) {
+            Ok(map) => map,
+            Err(e) => {
+                println!("FAIL {}: {}", name, e);
+                is_valid = false;
+                continue;
+            }
+        };
+
+        let mut problems = data.validate();
+        if map_file_name(data.x, data.y, data.group, format) != name {
+            problems.push(format!("File name does not match the map location [ X: {} Y: {} Group: {} ]", data.x, data.y, data.group));
+        }
+        if let Some(other_name) = locations.insert((data.x, data.y, data.group), name.clone()) {
+            problems.push(format!("Map location is also used by {}", other_name));
+        }
+
+        if problems.is_empty() {
+            if version < MAP_VERSION {
+                println!("OK   {} (version {}, will be upgraded to {})", name, version, MAP_VERSION);
+            } else {
+                println!("OK   {}", name);
+            }
+        } else {
+            is_valid = false;
+            for problem in problems {
+                println!("FAIL {}: {}", name, problem);
+            }
+        }
+    }
+    Ok(is_valid)
+}
+
+fn convert(args: &[String]) -> Result<bool, AscendingError> {
+    let format = match args.first().and_then(|name| MapFormat::from_name(name)) {
+        Some(format) => format,
+        None => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    let count = convert_maps(format)?;
+    let mut config = project_config();
+    config.map_format = format;
+    save_project_config(config)?;
+    println!("Converted {} map/s to {:?}", count, format);
+    Ok(true)
+}
+
+fn move_map(args: &[String]) -> Result<bool, AscendingError> {
+    let (from, to) = match (args.get(0..3).and_then(parse_location), args.get(3..6).and_then(parse_location)) {
+        (Some(from), Some(to)) if args.len() == 6 => (from, to),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    move_map_file(from, to)?;
+    println!("Moved map [ X: {} Y: {} Group: {} ] to [ X: {} Y: {} Group: {} ]", from.0, from.1, from.2, to.0, to.1, to.2);
+    Ok(true)
+}
+
+fn export(args: &[String]) -> Result<bool, AscendingError> {
+    let include_neighbours = !args.iter().any(|arg| arg == "--no-neighbours");
+    let args: Vec<String> = args.iter().filter(|arg| *arg != "--no-neighbours").cloned().collect();
+    let ((x, y, group), path) = match (args.get(0..3).and_then(parse_location), args.get(3)) {
+        (Some(location), Some(path)) if args.len() == 4 && TiledFormat::from_path(path).is_some() => (location, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    let count = export_tiled(path, x, y, group, include_neighbours)?;
+    println!("Exported {} map/s to {}", count, path);
+    Ok(true)
+}
+
+fn import(args: &[String]) -> Result<bool, AscendingError> {
+    let overwrite = args.iter().any(|arg| arg == "--overwrite");
+    let args: Vec<String> = args.iter().filter(|arg| *arg != "--overwrite").cloned().collect();
+    let (path, (x, y, group)) = match (args.first(), args.get(1..4).and_then(parse_location)) {
+        (Some(path), Some(location)) if args.len() == 4 => (path, location),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    let result = import_tiled(path, x, y, group, overwrite)?;
+    for (map_x, map_y) in result.maps.iter() {
+        println!("Imported map [ X: {} Y: {} Group: {} ]", map_x, map_y, group);
+    }
+    for message in result.unmapped.iter() {
+        println!("UNMAPPED {}", message);
+    }
+    Ok(true)
+}
+
+fn render(args: &[String]) -> Result<bool, AscendingError> {
+    let options = RenderOptions {
+        grid: args.iter().any(|arg| arg == "--grid"),
+        attributes: args.iter().any(|arg| arg == "--attributes"),
+    };
+    let mut args: Vec<String> = args.iter().filter(|arg| *arg != "--grid" && *arg != "--attributes").cloned().collect();
+    let to = match args.iter().position(|arg| arg == "--to") {
+        Some(index) => {
+            let to: Vec<String> = args.drain(index..).skip(1).collect();
+            match to.as_slice() {
+                [x, y] => match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => Some((x, y)),
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return Ok(false);
+                    }
+                },
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return Ok(false);
+                }
+            }
+        }
+        None => None,
+    };
+    let ((x, y, group), path) = match (args.get(0..3).and_then(parse_location), args.get(3)) {
+        (Some(location), Some(path)) if args.len() == 4 => (location, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    let count = render_maps_to_png(path, group, (x, y), to.unwrap_or((x, y)), options)?;
+    println!("Rendered {} map/s to {}", count, path);
+    Ok(true)
+}
+
+fn cleanup(args: &[String]) -> Result<bool, AscendingError> {
+    let dry_run = match args {
+        [] => false,
+        [flag] if flag == "--dry-run" => true,
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(false);
+        }
+    };
+    init_project()?;
+
+    let empty_maps = remove_empty_maps(dry_run)?;
+    for name in empty_maps.iter() {
+        println!("{} {}", if dry_run { "EMPTY" } else { "REMOVED" }, name);
+    }
+    if dry_run {
+        println!("Found {} empty map/s", empty_maps.len());
+    } else {
+        println!("Removed {} empty map/s", empty_maps.len());
+    }
+    Ok(true)
+}
+
+fn stats() -> Result<bool, AscendingError> {
+    init_project()?;
+    let (mut map_count, mut failed_count) = (0, 0);
+    let mut groups: BTreeMap<u64, usize> = BTreeMap::new();
+    let mut formats: BTreeMap<String, usize> = BTreeMap::new();
+    let mut versions: BTreeMap<u32, usize> = BTreeMap::new();
+    let mut tilesheets: BTreeMap<String, usize> = BTreeMap::new();
+    let mut attributes: BTreeMap<String, usize> = BTreeMap::new();
+
+    for (name, format) in list_map_files()? {
+        let (data, version) = match read_map_file(&name, format) {
+            Ok(map) => map,
+            Err(_) => {
+                failed_count += 1;
+                continue;
+            }
+        };
+        map_count += 1;
+        *groups.entry(data.group).or_default() += 1;
+        *formats.entry(format!("{:?}", format)).or_default() += 1;
+        *versions.entry(version).or_default() += 1;
+        for tile in data.tile.iter() {
+            for tile_ref in tile.id.iter().flatten() {
+                if let Some(sheet) = data.tilesheets.get(tile_ref.sheet as usize) {
+                    *tilesheets.entry(sheet.clone()).or_default() += 1;
+                }
+            }
+        }
+        for attribute in data.attribute.iter() {
+            if let Some(index) = attribute.get_index() {
+                *attributes.entry(MapAttribute::as_str(index).to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    println!("Maps: {} ({} could not be read)", map_count, failed_count);
+    println!("Project format: {:?}", map_format());
+    print_counts("Maps per group", &groups);
+    print_counts("Maps per format", &formats);
+    print_counts("Maps per version", &versions);
+    print_counts("Tiles per tilesheet", &tilesheets);
+    print_counts("Attributes", &attributes);
+    Ok(true)
+}
+
+fn print_counts<K: std::fmt::Display>(title: &str, counts: &BTreeMap<K, usize>) {
+    println!("{}:", title);
+    for (key, count) in counts.iter() {
+        println!("    {}: {}", key, count);
+    }
+}
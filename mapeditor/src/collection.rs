@@ -1,2 +1,2 @@
-pub const TEXTURE_SIZE: u32 = 20;
+pub const TEXTURE_SIZE: u32 = crate::map_data::TILE_SIZE;
 pub const ZOOM_LEVEL: f32 = 1.0;
\ No newline at end of file
@@ -3,6 +3,7 @@ use std::any::Any;
 use graphics::*;
 use serde::{Deserialize, Serialize};
 use cosmic_text::{Attrs, Metrics};
+use log::{error, warn};
 
 use winit::{
     keyboard::*,
@@ -29,6 +30,13 @@ use crate::interface::dialog::DialogButtonType;
 pub enum Action {
     Quit,
     Select,
+    Undo,
+    Redo,
+    WorldView,
+    Copy,
+    Cut,
+    Paste,
+    StampLibrary,
 }
 
 pub enum InputType {
@@ -44,7 +52,7 @@ enum PressType {
     PressMap,
 }
 
-pub const ACTION_SIZE: usize = 3;
+pub const ACTION_SIZE: usize = 9;
 
 pub struct GameInput {
     // General
@@ -55,9 +63,18 @@ pub struct GameInput {
     tileset_end: Vec2,
     return_size: Vec2,
     // Map
+    map_start: Vec2,
     selected_link_map: Option<usize>,
+    // Position of the selection when it started to be moved, None while a new selection is being picked
+    select_origin: Option<Vec2>,
+    // Internal clipboard, this is used when the OS clipboard does not have a map selection
+    clipboard: Option<MapClipboard>,
+    // The OS clipboard is kept open, on X11 the copied text is lost once the last clipboard is dropped
+    os_clipboard: Option<arboard::Clipboard>,
     pub dialog_button_press: bool,
     selected_dialog_type: DialogButtonType,
+    // The map browser needs the image atlas, so it is opened outside of the input handling
+    pub open_map_browser: bool,
 }
 
 impl GameInput {
@@ -68,17 +85,43 @@ impl GameInput {
             tileset_start: Vec2::new(0.0, 0.0),
             tileset_end: Vec2::new(0.0, 0.0),
             return_size: Vec2::new(1.0, 1.0),
+            map_start: Vec2::new(0.0, 0.0),
             selected_link_map: None,
+            select_origin: None,
+            clipboard: None,
+            os_clipboard: None,
             dialog_button_press: false,
             selected_dialog_type: DialogButtonType::ButtonNone,
+            open_map_browser: false,
         }
     }
+
+    // The OS clipboard is only opened when it is first used
+    fn os_clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.os_clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.os_clipboard = Some(clipboard),
+                Err(e) => {
+                    warn!("Failed to open the clipboard, Err {:?}", e);
+                    return None;
+                }
+            }
+        }
+        self.os_clipboard.as_mut()
+    }
 }
 
 pub fn action_index(action: Action) -> usize {
     match action {
         Action::Quit => 0,
         Action::Select => 1,
+        Action::Undo => 2,
+        Action::Redo => 3,
+        Action::WorldView => 4,
+        Action::Copy => 5,
+        Action::Cut => 6,
+        Action::Paste => 7,
+        Action::StampLibrary => 8,
     }
 }
 
@@ -114,7 +157,52 @@ fn get_map_pos(screen_pos: Vec2, mapview: &MapView) -> Vec2 {
     )
 }
 
+// Tile position relative to the main map, the linked map strips are placed on the 2 tiles around the main map
+fn get_view_pos(screen_pos: Vec2, mapview: &MapView) -> Option<Vec2> {
+    if in_map(screen_pos, mapview) {
+        return Some(get_map_pos(screen_pos, mapview));
+    }
+    let index = mapview.link_map_selection.iter().position(|selection| {
+        screen_pos.x >= selection.pos.x && screen_pos.x < selection.pos.x + selection.hw.x
+            && screen_pos.y >= selection.pos.y && screen_pos.y < selection.pos.y + selection.hw.y
+    })?;
+    let selection = &mapview.link_map_selection[index];
+    let tile_pos = ((screen_pos - Vec2::new(selection.pos.x, selection.pos.y)) / TEXTURE_SIZE as f32).floor();
+    let offset = match index {
+        0 => Vec2::new(-2.0, 32.0), // Top Left
+        1 => Vec2::new(0.0, 32.0), // Top
+        2 => Vec2::new(32.0, 32.0), // Top Right
+        3 => Vec2::new(-2.0, 0.0), // Left
+        4 => Vec2::new(32.0, 0.0), // Right
+        5 => Vec2::new(-2.0, -2.0), // Bottom Left
+        6 => Vec2::new(0.0, -2.0), // Bottom
+        _ => Vec2::new(32.0, -2.0), // Bottom Right
+    };
+    Some(tile_pos + offset)
+}
+
+// The shape bounds are shown on the selection preview while the shape is being dragged
+fn preview_shape_bounds(mapview: &mut MapView, start_pos: Vec2, end_pos: Vec2) {
+    let min_pos = start_pos.min(end_pos).max(Vec2::new(0.0, 0.0));
+    let max_pos = start_pos.max(end_pos).min(Vec2::new(31.0, 31.0));
+    mapview.hover_selection_preview(min_pos);
+    mapview.change_selection_preview_size(max_pos - min_pos + 1.0);
+}
+
+// The random brush paints a single tile at a time, the tileset selection is only its palette
+fn brush_preview_size(gui: &Interface, tileset: &Tileset) -> Vec2 {
+    if gui.random_brush && gui.current_tool == TOOL_DRAW && tileset.library_stamp.is_none() {
+        Vec2::new(1.0, 1.0)
+    } else {
+        tileset.get_brush_size()
+    }
+}
+
+// The start position is where the mouse was pressed, this is used by the tools that are dragged such as the shapes
+#[allow(clippy::too_many_arguments)]
 fn interact_with_map(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    start_pos: Vec2,
                     tile_pos: Vec2, 
                     gui: &mut Interface,
                     tileset: &mut Tileset,
@@ -125,38 +213,284 @@ fn interact_with_map(renderer: &mut GpuRenderer,
         TAB_LAYER => {
             match gui.current_tool {
                 TOOL_DRAW => {
-                    mapview.set_tile_group(tile_pos, gui.get_tab_option_data(), 
-                                    &tileset.map, 
-                                    tileset.select_start, 
-                                    tileset.select_size);
+                    if let Some(library_stamp) = &tileset.library_stamp {
+                        library_stamp.place(resource, &mut mapview.document, tile_pos);
+                    } else if gui.random_brush {
+                        let palette = TilePalette::new(&tileset.get_brush(), &tileset.get_brush_weights());
+                        mapview.document.set_tile_random(tile_pos, gui.get_tab_option_data(), &palette, gui.brush_seed);
+                    } else {
+                        mapview.document.set_tile_group(tile_pos, gui.get_tab_option_data(), &tileset.get_brush());
+                    }
+                    editor_data.apply_linked_changes(resource, &mut mapview.document);
                     if editor_data.set_map_change() {
                         update_map_name(renderer, gui, editor_data);
                     };
                 },
                 TOOL_ERASE => {
-                    mapview.delete_tile_group(tile_pos, gui.get_tab_option_data(),  
-                                tileset.select_size);
+                    mapview.document.delete_tile_group(tile_pos, gui.get_tab_option_data(),  
+                                tileset.get_brush_size());
+                    editor_data.apply_linked_changes(resource, &mut mapview.document);
                     if editor_data.set_map_change() {
                         update_map_name(renderer, gui, editor_data);
                     };
                 },
                 TOOL_FILL => {
-                    mapview.set_tile_fill(tile_pos, gui.get_tab_option_data(), 
-                                    &tileset.map, 
-                                    tileset.select_start);
+                    if gui.random_brush {
+                        let palette = TilePalette::new(&tileset.get_brush(), &tileset.get_brush_weights());
+                        mapview.document.set_tile_fill_random(tile_pos, gui.get_tab_option_data(), &palette, gui.brush_seed);
+                    } else {
+                        mapview.document.set_tile_fill(tile_pos, gui.get_tab_option_data(), tileset.get_brush_tile(0, 0));
+                    }
+                    editor_data.apply_linked_changes(resource, &mut mapview.document);
                     if editor_data.set_map_change() {
                         update_map_name(renderer, gui, editor_data);
                     };
-                }
+                },
+                TOOL_SHAPE => {
+                    mapview.document.set_tile_shape(gui.current_shape, start_pos, tile_pos, gui.get_tab_option_data(), &tileset.get_brush());
+                    if editor_data.set_map_change() {
+                        update_map_name(renderer, gui, editor_data);
+                    };
+                },
+                TOOL_TERRAIN => {
+                    let terrain = match gui.get_terrain() {
+                        Some(terrain) => terrain,
+                        None => return,
+                    };
+                    paint_terrain(&mut mapview.document, resource, terrain, tile_pos, gui.get_tab_option_data());
+                    editor_data.apply_linked_changes(resource, &mut mapview.document);
+                    if editor_data.set_map_change() {
+                        update_map_name(renderer, gui, editor_data);
+                    };
+                },
                 _ => {},
             }
         }
-        TAB_ATTRIBUTE => {},
+        TAB_ATTRIBUTE => {
+            match gui.current_tool {
+                TOOL_DRAW => {
+                    mapview.document.set_attribute(tile_pos, gui.attribute_setting.get_attribute());
+                    if editor_data.set_map_change() {
+                        update_map_name(renderer, gui, editor_data);
+                    };
+                },
+                TOOL_ERASE => {
+                    mapview.document.delete_attribute(tile_pos);
+                    if editor_data.set_map_change() {
+                        update_map_name(renderer, gui, editor_data);
+                    };
+                },
+                TOOL_FILL => {
+                    mapview.document.set_attribute_fill(tile_pos, gui.attribute_setting.get_attribute());
+                    if editor_data.set_map_change() {
+                        update_map_name(renderer, gui, editor_data);
+                    };
+                }
+                _ => {},
+            }
+        },
         TAB_PROPERTIES => {},
         _ => {},
     }
 }
 
+// Move the selected tiles by the offset from where the selection was when the drag started
+// The tiles are moved again from their original position on every mouse move, so the move is a single undo
+fn move_selection(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    mapview: &mut MapView,
+                    editor_data: &mut EditorData,
+                    origin: Vec2,
+                    offset: Vec2)
+{
+    let selection = match mapview.selection {
+        Some(selection) => selection,
+        None => return,
+    };
+    let new_pos = (origin + offset).clamp(Vec2::ZERO, Vec2::splat(32.0) - selection.size);
+    if new_pos == selection.pos {
+        return;
+    }
+
+    mapview.document.move_region(resource, origin, selection.size, new_pos, &gui.selection_layers(), gui.select_all_layers);
+    if new_pos != origin && editor_data.set_map_change() {
+        update_map_name(renderer, gui, editor_data);
+    }
+    mapview.set_selection(new_pos, new_pos + selection.size - 1.0);
+}
+
+// The clipboard shortcuts are not used while a text is being typed
+fn is_editing_text(gui: &Interface) -> bool {
+    match gui.current_setting_tab {
+        TAB_ATTRIBUTE => gui.attribute_setting.editing_index.is_some(),
+        TAB_PROPERTIES => gui.property_setting.editing_index.is_some(),
+        _ => false,
+    }
+}
+
+// Copy, cut or paste the map selection, the selection is kept on the internal clipboard and on the OS clipboard as JSON
+// The pasted tiles become the selection so they can be moved right away
+pub fn handle_clipboard(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gameinput: &mut GameInput,
+                    gui: &mut Interface,
+                    mapview: &mut MapView,
+                    editor_data: &mut EditorData,
+                    action: Action)
+{
+    if gui.dialog.is_some() || gui.world_view.is_some() || gui.map_browser.is_some() || gui.stamp_browser.is_some()
+        || gui.current_tool != TOOL_SELECT || is_editing_text(gui) {
+        return;
+    }
+
+    match action {
+        Action::Copy | Action::Cut => {
+            let selection = match mapview.selection {
+                Some(selection) => selection,
+                None => return,
+            };
+            let layers = gui.selection_layers();
+            let copied = MapClipboard::copy(resource, &mapview.document, selection.pos, selection.size, &layers, gui.select_all_layers);
+            match copied.to_json() {
+                Ok(json) => {
+                    if let Some(Err(e)) = gameinput.os_clipboard().map(|clipboard| clipboard.set_text(json)) {
+                        warn!("Failed to place the selection on the clipboard, Err {:?}", e);
+                    }
+                },
+                Err(e) => warn!("Failed to write the selection, Err {:?}", e),
+            }
+            gameinput.clipboard = Some(copied);
+
+            if action == Action::Cut {
+                mapview.document.record().set_record();
+                mapview.document.delete_region(selection.pos, selection.size, &layers, gui.select_all_layers);
+                mapview.document.stop_records();
+                if editor_data.set_map_change() {
+                    update_map_name(renderer, gui, editor_data);
+                }
+            }
+        },
+        Action::Paste => {
+            // The OS clipboard is checked first so a selection can be pasted from another editor
+            let pasted = gameinput.os_clipboard()
+                .and_then(|clipboard| clipboard.get_text().ok())
+                .and_then(|text| MapClipboard::from_json(&text).ok())
+                .or_else(|| gameinput.clipboard.clone());
+            let pasted = match pasted {
+                Some(pasted) => pasted,
+                None => return,
+            };
+
+            // The tiles are pasted on the selection or on the tile below the mouse, the whole selection must fit the map
+            let size = pasted.size();
+            let pos = mapview.selection.map(|selection| selection.pos)
+                .unwrap_or_else(|| mapview.get_preview_pos())
+                .clamp(Vec2::ZERO, Vec2::splat(32.0) - size);
+            mapview.document.record().set_record();
+            pasted.paste(resource, &mut mapview.document, pos);
+            mapview.document.stop_records();
+            mapview.set_selection(pos, pos + size - 1.0);
+            if editor_data.set_map_change() {
+                update_map_name(renderer, gui, editor_data);
+            }
+        },
+        _ => {},
+    }
+}
+
+// Scrolling over a tile of the tileset changes its weight on the random brush
+pub fn adjust_tile_weight(renderer: &mut GpuRenderer,
+                    gui: &mut Interface,
+                    tileset: &mut Tileset,
+                    screen_pos: Vec2,
+                    amount: f32)
+{
+    if !gui.random_brush || gui.tileset_list.visible || gui.dialog.is_some() || gui.map_browser.is_some()
+        || gui.stamp_browser.is_some() || !in_tileset(screen_pos, tileset) {
+        return;
+    }
+    let tile_pos = get_tileset_pos(screen_pos, tileset).min(Vec2::new((MAX_TILE_X - 1) as f32, (MAX_TILE_Y - 1) as f32));
+    let weight = tileset.adjust_weight(tile_pos, amount.signum() as i32);
+    gui.labels[LABEL_TOOL_OPTION].set_text(renderer, &format!("Random: Tile Weight {}", weight), Attrs::new());
+}
+
+// Apply the undo or redo on the current map and mark the map as changed
+pub fn apply_map_history(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    mapview: &mut MapView,
+                    editor_data: &mut EditorData,
+                    is_redo: bool)
+{
+    if gui.dialog.is_some() || gui.world_view.is_some() {
+        return;
+    }
+
+    let did_apply = if is_redo {
+        mapview.document.apply_redo()
+    } else {
+        mapview.document.apply_undo()
+    };
+    if did_apply {
+        // The tiles of the stroke that were on the linked maps are put back on their map data
+        editor_data.apply_linked_changes(resource, &mut mapview.document);
+        if editor_data.set_map_change() {
+            update_map_name(renderer, gui, editor_data);
+        }
+    }
+}
+
+// Pick the tiles on the map area and set them as the current selection of the tileset
+fn pick_map_tiles(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    tileset: &mut Tileset,
+                    mapview: &mut MapView,
+                    start: Vec2,
+                    end: Vec2) -> Option<Vec2>
+{
+    let layer = gui.get_tab_option_data();
+    let max_pos = Vec2::new(31.0, 31.0);
+    let start_pos = start.min(end).min(max_pos);
+    let size = (start.max(end).min(max_pos) - start_pos) + 1.0;
+    let tiles = mapview.document.get_tile_area(start_pos, size, layer);
+
+    // The first tile that is not empty deturmine which tilesheet will be selected
+    let (offset, (name, tile_index)) = tiles.iter().enumerate().find_map(|(index, tile)| {
+        let tile_ref = resource.get_tile_ref(tile.texture_id, tile.texture_layer)?;
+        Some((index as u32, tile_ref))
+    })?;
+    let tileset_index = resource.get_tilesheet_index(name)?;
+    let tile_pos = Tileset::get_tile_pos(resource, tileset_index, tile_index)?;
+
+    if tileset.selected_tile != tileset_index {
+        gui.labels[LABEL_TILESET].set_text(renderer, &resource.tilesheet[tileset_index].name, Attrs::new());
+        tileset.change_tileset(resource, tileset_index);
+        gui.tileset_list.set_selected_tileset(tileset_index);
+    }
+
+    // Check if the picked tiles can be found with the same arrangement on the tileset
+    let base_pos = tile_pos - Vec2::new((offset % size.x as u32) as f32, (offset / size.x as u32) as f32);
+    let end_pos = base_pos + size - 1.0;
+    let in_tileset = base_pos.x >= 0.0 && base_pos.y >= 0.0
+        && end_pos.x < MAX_TILE_X as f32 && end_pos.y < MAX_TILE_Y as f32;
+    let same_arrangement = in_tileset && tiles.iter().enumerate().all(|(index, tile)| {
+        let (x, y) = (index as u32 % size.x as u32, index as u32 / size.x as u32);
+        let tileset_tile = tileset.map.get_tile((base_pos.x as u32 + x, base_pos.y as u32 + y, 0));
+        tileset_tile.texture_id == tile.texture_id && tileset_tile.texture_layer == tile.texture_layer
+    });
+
+    if same_arrangement {
+        Some(tileset.set_selection(base_pos, end_pos))
+    } else {
+        // Highlight the first picked tile and keep the picked tiles as a stamp
+        tileset.set_selection(tile_pos, tile_pos);
+        tileset.stamp = Some(TileStamp { size, tiles });
+        Some(size)
+    }
+}
+
 pub fn update_map_name(renderer: &mut GpuRenderer,
                     gui: &mut Interface,
                     editor_data: &mut EditorData)
@@ -168,9 +502,287 @@ pub fn update_map_name(renderer: &mut GpuRenderer,
     }
 }
 
+// Show the properties of the map that is currently on the main view
+pub fn load_map_properties(renderer: &mut GpuRenderer,
+                    gui: &mut Interface,
+                    editor_data: &mut EditorData)
+{
+    if let Some(properties) = editor_data.get_properties() {
+        gui.property_setting.load_properties(renderer, properties);
+    }
+}
+
+// List the maps that were upgraded from an older map version, this waits until no other dialog is open
+pub fn show_migrated_maps(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64,
+                    editor_data: &mut EditorData)
+{
+    if gui.dialog.is_some() || editor_data.migrated_maps.is_empty() {
+        return;
+    }
+    let migrated_maps = std::mem::take(&mut editor_data.migrated_maps);
+    gui.open_dialog(resource, renderer, screen_size, scale, DialogType::TypeMapMigrated, Some(migrated_maps));
+}
+
+// Save the values on the properties tab to the map that is currently on the main view
+fn apply_map_properties(renderer: &mut GpuRenderer,
+                    gui: &mut Interface,
+                    editor_data: &mut EditorData)
+{
+    if let Some(properties) = editor_data.get_properties_mut() {
+        let last_properties = properties.clone();
+        gui.property_setting.apply_properties(properties);
+        if *properties != last_properties && editor_data.set_map_change() {
+            update_map_name(renderer, gui, editor_data);
+        }
+    }
+}
+
+// Keep the unsaved changes of the current map before we switch,
+// otherwise its history will no longer match the map
+fn keep_current_map(resource: &TextureAllocation,
+                    editor_data: &mut EditorData,
+                    mapview: &MapView)
+{
+    if editor_data.did_change(editor_data.x, editor_data.y, editor_data.group) {
+        let current_key = editor_data.current_index.clone();
+        // Temporary data is never written on a file, so this will not fail
+        let _ = editor_data.save_map_data(resource, &mapview.document, Some(current_key));
+    }
+}
+
+// Load the map at the location on the main view
+#[allow(clippy::too_many_arguments)]
+pub fn switch_map(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64,
+                    editor_data: &mut EditorData,
+                    mapview: &mut MapView,
+                    x: i32,
+                    y: i32,
+                    group: u64)
+{
+    keep_current_map(resource, editor_data, mapview);
+    match editor_data.init_map(x, y, group) {
+        Ok(()) => {
+            editor_data.load_map_data(resource, &mut mapview.document);
+            editor_data.load_link_maps(resource, &mut mapview.document);
+            update_map_name(renderer, gui, editor_data);
+            load_map_properties(renderer, gui, editor_data);
+            show_migrated_maps(renderer, resource, gui, screen_size, scale, editor_data);
+        },
+        Err(e) => {
+            gui.open_error_dialog(resource, renderer, screen_size, scale, &e.to_string());
+        },
+    }
+}
+
+// Show the overview of the maps on the current group, the current map is kept first so its thumbnail is up to date
+#[allow(clippy::too_many_arguments)]
+pub fn open_world_view(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    atlas: &mut AtlasSet,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64,
+                    editor_data: &mut EditorData,
+                    mapview: &MapView)
+{
+    if gui.dialog.is_some() || gui.map_browser.is_some() {
+        return;
+    }
+    keep_current_map(resource, editor_data, mapview);
+    gui.world_view = Some(WorldView::new(resource, renderer, atlas, screen_size, scale, editor_data));
+}
+
+// Jump to the map that has been clicked on the world view
+pub fn release_world_view(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64,
+                    editor_data: &mut EditorData,
+                    mapview: &mut MapView)
+{
+    let selected = match &mut gui.world_view {
+        Some(world_view) => world_view.release().map(|(x, y)| (x, y, world_view.group)),
+        None => return,
+    };
+    if let Some((x, y, group)) = selected {
+        gui.world_view = None;
+        switch_map(renderer, resource, gui, screen_size, scale, editor_data, mapview, x, y, group);
+    }
+}
+
+// List the maps that can be opened, the current map is kept first so its thumbnail is up to date
+#[allow(clippy::too_many_arguments)]
+pub fn open_map_browser(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    atlas: &mut AtlasSet,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64,
+                    editor_data: &mut EditorData,
+                    mapview: &MapView)
+{
+    if gui.dialog.is_some() {
+        return;
+    }
+    keep_current_map(resource, editor_data, mapview);
+    gui.map_browser = Some(MapBrowser::new(resource, renderer, atlas, screen_size, scale, editor_data));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_map_browser_input(renderer: &mut GpuRenderer,
+                            resource: &TextureAllocation,
+                            gameinput: &mut GameInput,
+                            gui: &mut Interface,
+                            screen_size: &PhysicalSize<f32>,
+                            scale: f64,
+                            editor_data: &mut EditorData,
+                            mapview: &mut MapView)
+{
+    if !gameinput.dialog_button_press || gui.dialog.is_some() {
+        return;
+    }
+    let selected = match &gui.map_browser {
+        Some(map_browser) => map_browser.get_selected(),
+        None => {
+            gameinput.dialog_button_press = false;
+            return;
+        },
+    };
+
+    gameinput.dialog_button_press = false;
+    match gameinput.selected_dialog_type {
+        DialogButtonType::ButtonConfirm => {
+            if let Some((x, y, group)) = selected {
+                gui.map_browser = None;
+                switch_map(renderer, resource, gui, screen_size, scale, editor_data, mapview, x, y, group);
+            }
+        },
+        DialogButtonType::ButtonDecline => {
+            // Creating a map is done on its own dialog so that a map is never created by mistake
+            gui.map_browser = None;
+            gui.open_dialog(resource, renderer, screen_size, scale, DialogType::TypeMapNew, None);
+        },
+        DialogButtonType::ButtonCancel => { gui.map_browser = None; },
+        _ => {},
+    }
+}
+
+pub fn open_stamp_browser(renderer: &mut GpuRenderer,
+                    resource: &TextureAllocation,
+                    gui: &mut Interface,
+                    screen_size: &PhysicalSize<f32>,
+                    scale: f64)
+{
+    if gui.dialog.is_some() || gui.map_browser.is_some() || gui.world_view.is_some() {
+        return;
+    }
+    match StampLibrary::load() {
+        Ok(library) => {
+            gui.stamp_browser = Some(StampBrowser::new(resource, renderer, screen_size, scale, library));
+        },
+        Err(e) => {
+            error!("Failed to load the stamp library, Err {:?}", e);
+            gui.open_error_dialog(resource, renderer, screen_size, scale, "The stamp library could not be read, please check the log");
+        },
+    }
+}
+
+// The map selection is saved with every layer, otherwise the brush is saved on the current layer
+fn capture_stamp(resource: &TextureAllocation,
+                    gui: &Interface,
+                    tileset: &Tileset,
+                    mapview: &MapView,
+                    name: &str) -> LibraryStamp
+{
+    if let Some(selection) = mapview.selection {
+        return LibraryStamp::from_map(resource, &mapview.document, name, selection.pos, selection.size);
+    }
+    match &tileset.library_stamp {
+        Some(library_stamp) => LibraryStamp { name: name.to_string(), ..library_stamp.clone() },
+        None => LibraryStamp::from_tile_stamp(resource, name, gui.current_tab_data, &tileset.get_brush()),
+    }
+}
+
+pub fn handle_stamp_browser_input(renderer: &mut GpuRenderer,
+                            resource: &TextureAllocation,
+                            gameinput: &mut GameInput,
+                            gui: &mut Interface,
+                            tileset: &mut Tileset,
+                            mapview: &mut MapView)
+{
+    if !gameinput.dialog_button_press || gui.dialog.is_some() || gui.stamp_browser.is_none() {
+        return;
+    }
+
+    gameinput.dialog_button_press = false;
+    match gameinput.selected_dialog_type {
+        DialogButtonType::ButtonConfirm => {
+            let library_stamp = match gui.stamp_browser.as_ref().and_then(|stamp_browser| stamp_browser.get_selected()) {
+                Some(library_stamp) => library_stamp.clone(),
+                None => return,
+            };
+            gui.stamp_browser = None;
+            tileset.use_library_stamp(resource, library_stamp);
+            mapview.clear_selection();
+            gui.set_tab(TAB_LAYER);
+            gui.set_tool(TOOL_DRAW);
+            gui.update_tool_label(renderer);
+            mapview.change_selection_preview_size(brush_preview_size(gui, tileset));
+        },
+        DialogButtonType::ButtonDecline => {
+            let name = match &gui.stamp_browser {
+                Some(stamp_browser) => stamp_browser.name_data.trim().to_string(),
+                None => return,
+            };
+            let library_stamp = capture_stamp(resource, gui, tileset, mapview, &name);
+            if let Some(stamp_browser) = &mut gui.stamp_browser {
+                if name.is_empty() {
+                    stamp_browser.set_message(renderer, "Please enter a name for the stamp");
+                } else if library_stamp.is_empty() {
+                    stamp_browser.set_message(renderer, "The selection does not have any tile");
+                } else {
+                    match stamp_browser.add_stamp(resource, renderer, library_stamp) {
+                        Ok(()) => stamp_browser.set_message(renderer, &format!("Stamp {} has been saved", name)),
+                        Err(e) => {
+                            error!("Failed to save the stamp library, Err {:?}", e);
+                            stamp_browser.set_message(renderer, "The stamp could not be saved, please check the log");
+                        },
+                    }
+                }
+            }
+        },
+        DialogButtonType::ButtonDelete => {
+            if let Some(stamp_browser) = &mut gui.stamp_browser {
+                match stamp_browser.remove_selected(resource, renderer) {
+                    Ok(Some(name)) => stamp_browser.set_message(renderer, &format!("Stamp {} has been deleted", name)),
+                    Ok(None) => {},
+                    Err(e) => {
+                        error!("Failed to save the stamp library, Err {:?}", e);
+                        stamp_browser.set_message(renderer, "The stamp could not be deleted, please check the log");
+                    },
+                }
+            }
+        },
+        DialogButtonType::ButtonCancel => { gui.stamp_browser = None; },
+        _ => {},
+    }
+}
+
 pub fn handle_dialog_input(renderer: &mut GpuRenderer,
+                            resource: &TextureAllocation,
                             gameinput: &mut GameInput,
                             gui: &mut Interface,
+                            screen_size: &PhysicalSize<f32>,
+                            scale: f64,
                             elwt: &winit::event_loop::EventLoopWindowTarget<()>,
                             editor_data: &mut EditorData,
                             mapview: &mut MapView) 
@@ -187,7 +799,7 @@ pub fn handle_dialog_input(renderer: &mut GpuRenderer,
             DialogButtonType::ButtonConfirm => {
                 match dialogtype {
                     DialogType::TypeExitConfirm => {elwt.exit()},
-                    DialogType::TypeMapLoad => {
+                    DialogType::TypeMapNew => {
                         let (mut x, mut y, mut group) = (0 as i32,0 as i32,0 as u64);
                         for (index, data) in dialog_data.editor_data.iter().enumerate() {
                             let value = data.parse::<i64>().unwrap_or_default();
@@ -197,22 +809,49 @@ pub fn handle_dialog_input(renderer: &mut GpuRenderer,
                                 _ => { x = value as i32; }
                             }
                         }
-                        editor_data.init_map(x, y, group);
-                        editor_data.load_map_data(mapview);
-                        editor_data.load_link_maps(mapview);
-                        update_map_name(renderer, gui, editor_data);
                         gui.close_dialog();
+                        // The map browser is used to open the maps that already exist
+                        if is_map_exist(x, y, group) || editor_data.maps.contains_key(&format!("{}_{}_{}", x, y, group)) {
+                            gui.open_error_dialog(resource, renderer, screen_size, scale, &format!("Map [ X: {} Y: {} Group: {} ] already exist", x, y, group));
+                        } else {
+                            switch_map(renderer, resource, gui, screen_size, scale, editor_data, mapview, x, y, group);
+                        }
                     },
                     DialogType::TypeMapSave => {
-                        editor_data.save_all_maps();
-                        elwt.exit()
+                        match editor_data.save_all_maps() {
+                            Ok(()) => elwt.exit(),
+                            Err(e) => {
+                                // We will not exit so that the changes will not be lost
+                                error!("Failed to save the maps, Err {:?}", e);
+                                gui.close_dialog();
+                                gui.open_error_dialog(resource, renderer, screen_size, scale, "Some maps could not be saved, please check the log");
+                            },
+                        }
+                    },
+                    DialogType::TypeMapRecovery => {
+                        gui.close_dialog();
+                        editor_data.restore_maps(load_recovery_maps());
+                        editor_data.load_map_data(resource, &mut mapview.document);
+                        editor_data.load_link_maps(resource, &mut mapview.document);
+                        update_map_name(renderer, gui, editor_data);
+                        load_map_properties(renderer, gui, editor_data);
+                        show_migrated_maps(renderer, resource, gui, screen_size, scale, editor_data);
                     },
                     _ => {},
                 }
             },
             DialogButtonType::ButtonDecline => {
                 match dialogtype {
-                    DialogType::TypeMapSave => {elwt.exit()},
+                    DialogType::TypeMapSave => {
+                        // The changes are discarded, so there is nothing left to recover
+                        clear_recovery_maps();
+                        elwt.exit()
+                    },
+                    DialogType::TypeMapRecovery => {
+                        clear_recovery_maps();
+                        gui.close_dialog();
+                        show_migrated_maps(renderer, resource, gui, screen_size, scale, editor_data);
+                    },
                     _ => {},
                 }
             },
@@ -242,7 +881,7 @@ pub fn handle_input(renderer: &mut GpuRenderer,
     if let Some(dialog) = &mut gui.dialog {
         match inputtype {
             InputType::MouseLeftDown => {
-                if dialog.dialog_type == DialogType::TypeMapSave {
+                if dialog.is_list() {
                     if dialog.scrollbar.in_scrollbar(screen_pos) {
                         dialog.scrollbar.hold_scrollbar(screen_pos.y);
                     }
@@ -255,7 +894,7 @@ pub fn handle_input(renderer: &mut GpuRenderer,
                 }
             }
             InputType::MouseLeftDownMove => {
-                if dialog.dialog_type == DialogType::TypeMapSave {
+                if dialog.is_list() {
                     // Update our tileset list based on the scrollbar value
                     dialog.scrollbar.move_scrollbar(screen_pos.y);
                     if dialog.update_scroll(dialog.scrollbar.cur_value) {
@@ -273,6 +912,72 @@ pub fn handle_input(renderer: &mut GpuRenderer,
         return;
     }
 
+    if let Some(map_browser) = &mut gui.map_browser {
+        match inputtype {
+            InputType::MouseLeftDown => {
+                if map_browser.scrollbar.in_scrollbar(screen_pos) {
+                    map_browser.scrollbar.hold_scrollbar(screen_pos.y);
+                }
+
+                if !map_browser.scrollbar.in_hold {
+                    gameinput.selected_dialog_type = map_browser.click_buttons(screen_pos);
+                    gameinput.dialog_button_press = true;
+                    map_browser.select_filter(screen_pos);
+                    map_browser.select_row(renderer, screen_pos);
+                }
+            }
+            InputType::MouseLeftDownMove => {
+                map_browser.scrollbar.move_scrollbar(screen_pos.y);
+                if map_browser.update_scroll(map_browser.scrollbar.cur_value) {
+                    map_browser.update_list(renderer);
+                }
+                map_browser.scrollbar.set_hover(screen_pos);
+            }
+            InputType::MouseMove => {
+                map_browser.hover(renderer, screen_pos);
+            }
+        }
+        return;
+    }
+
+    if let Some(stamp_browser) = &mut gui.stamp_browser {
+        match inputtype {
+            InputType::MouseLeftDown => {
+                if stamp_browser.scrollbar.in_scrollbar(screen_pos) {
+                    stamp_browser.scrollbar.hold_scrollbar(screen_pos.y);
+                }
+
+                if !stamp_browser.scrollbar.in_hold {
+                    gameinput.selected_dialog_type = stamp_browser.click_buttons(screen_pos);
+                    gameinput.dialog_button_press = true;
+                    stamp_browser.select_textbox(screen_pos);
+                    stamp_browser.select_row(renderer, screen_pos);
+                }
+            }
+            InputType::MouseLeftDownMove => {
+                stamp_browser.scrollbar.move_scrollbar(screen_pos.y);
+                if stamp_browser.update_scroll(stamp_browser.scrollbar.cur_value) {
+                    stamp_browser.update_list(renderer);
+                }
+                stamp_browser.scrollbar.set_hover(screen_pos);
+            }
+            InputType::MouseMove => {
+                stamp_browser.hover(renderer, screen_pos);
+            }
+        }
+        return;
+    }
+
+    // The world view covers the editor, so it takes all the inputs while open
+    if let Some(world_view) = &mut gui.world_view {
+        match inputtype {
+            InputType::MouseLeftDown => world_view.hold(screen_pos),
+            InputType::MouseLeftDownMove => world_view.drag(screen_pos),
+            InputType::MouseMove => world_view.hover(renderer, screen_pos),
+        }
+        return;
+    }
+
     match inputtype {
         InputType::MouseLeftDown => {
             if gui.tileset_list.scrollbar.in_scrollbar(screen_pos) {
@@ -287,30 +992,65 @@ pub fn handle_input(renderer: &mut GpuRenderer,
                     gameinput.tileset_start = tile_map_pos.clone();
                     gameinput.tileset_end = tile_map_pos.clone();
                     gameinput.return_size = tileset.set_selection(gameinput.tileset_start, gameinput.tileset_end);
-                    mapview.change_selection_preview_size(gameinput.return_size);
+                    if gui.current_setting_tab != TAB_ATTRIBUTE {
+                        mapview.change_selection_preview_size(brush_preview_size(gui, tileset));
+                    }
 
                     gameinput.presstype = PressType::PressTileset;
                 }
 
                 // Check if mouse position is pointing to our map view
                 if in_map(screen_pos, mapview) {
-                    mapview.record.set_record();
-                    interact_with_map(renderer,get_map_pos(screen_pos, mapview), gui, tileset, mapview, editor_data);
+                    let tile_map_pos = get_map_pos(screen_pos, mapview);
+                    gameinput.map_start = tile_map_pos;
+                    if gui.current_tool == TOOL_SELECT {
+                        // Pressing inside the selection starts moving it, otherwise a new selection is started
+                        gameinput.select_origin = mapview.selection
+                            .filter(|selection| selection.contains(tile_map_pos))
+                            .map(|selection| selection.pos);
+                        if gameinput.select_origin.is_some() {
+                            mapview.document.record().set_record();
+                        } else {
+                            mapview.set_selection(tile_map_pos, tile_map_pos);
+                        }
+                    } else if gui.current_tool == TOOL_EYEDROP && gui.current_setting_tab == TAB_ATTRIBUTE {
+                        gui.attribute_setting.set_attribute(renderer, mapview.document.get_attribute(tile_map_pos));
+                    } else if gui.current_tool == TOOL_EYEDROP {
+                        if let Some(size) = pick_map_tiles(renderer, resource, gui, tileset, mapview, tile_map_pos, tile_map_pos) {
+                            gameinput.return_size = size;
+                            mapview.change_selection_preview_size(size);
+                        }
+                    } else {
+                        gui.brush_seed = stroke_seed();
+                        mapview.document.record().set_record();
+                        interact_with_map(renderer, resource, tile_map_pos, tile_map_pos, gui, tileset, mapview, editor_data);
+                        if gui.current_tool == TOOL_SHAPE && gui.current_setting_tab == TAB_LAYER {
+                            preview_shape_bounds(mapview, tile_map_pos, tile_map_pos);
+                        }
+                    }
                     gameinput.presstype = PressType::PressMap;
                 }
 
                 // Linked Map
                 if gameinput.selected_link_map.is_some() {
                     let direction = convert_to_dir(gameinput.selected_link_map.unwrap());
-                    let temp_key = editor_data.move_map(direction);
-                    if temp_key.is_some() {
-                        // We will store a temporary map data when changes happen
-                        editor_data.save_map_data(&mapview.maps[0], temp_key);
-                    };
-                    // Load the initial map
-                    editor_data.load_map_data(mapview);
-                    editor_data.load_link_maps(mapview);
-                    update_map_name(renderer, gui, editor_data);
+                    match editor_data.move_map(direction) {
+                        Ok(temp_key) => {
+                            if temp_key.is_some() {
+                                // We will store a temporary map data when changes happen
+                                let _ = editor_data.save_map_data(resource, &mapview.document, temp_key);
+                            };
+                            // Load the initial map
+                            editor_data.load_map_data(resource, &mut mapview.document);
+                            editor_data.load_link_maps(resource, &mut mapview.document);
+                            update_map_name(renderer, gui, editor_data);
+                            load_map_properties(renderer, gui, editor_data);
+                            show_migrated_maps(renderer, resource, gui, screen_size, scale, editor_data);
+                        },
+                        Err(e) => {
+                            gui.open_error_dialog(resource, renderer, screen_size, scale, &e.to_string());
+                        },
+                    }
                 }
 
                 // Tools
@@ -319,21 +1059,65 @@ pub fn handle_input(renderer: &mut GpuRenderer,
                     let button_index = click_button.unwrap();
                     match button_index {
                         TOOL_LOAD => {
-                            gui.open_dialog(resource, renderer, screen_size, scale, DialogType::TypeMapLoad, None);
+                            gameinput.open_map_browser = true;
                         },
                         TOOL_SAVE => { 
-                            editor_data.save_map_data(&mapview.maps[0], None);
+                            if let Err(e) = editor_data.save_map_data(resource, &mapview.document, None) {
+                                error!("Failed to save map {}, Err {:?}", editor_data.current_index, e);
+                                gui.open_error_dialog(resource, renderer, screen_size, scale, "The map could not be saved, please check the log");
+                            }
                             update_map_name(renderer, gui, editor_data);
                         },
                         TOOL_UNDO => {
-                            mapview.apply_undo();
+                            apply_map_history(renderer, resource, gui, mapview, editor_data, false);
                         },
-                        TOOL_EYEDROP => { println!("To Do") },
-                        TOOL_DRAW | TOOL_ERASE | TOOL_FILL => {
+                        TOOL_REDO => {
+                            apply_map_history(renderer, resource, gui, mapview, editor_data, true);
+                        },
+                        TOOL_DRAW | TOOL_ERASE | TOOL_FILL | TOOL_EYEDROP | TOOL_SHAPE => {
+                            // Clicking on the shape tool while it is being used switch to the next shape
+                            if button_index == TOOL_SHAPE && gui.current_tool == TOOL_SHAPE {
+                                gui.current_shape = gui.current_shape.next();
+                            }
+                            // Clicking on the draw or fill tool while it is being used switch the random brush
+                            if matches!(button_index, TOOL_DRAW | TOOL_FILL) && gui.current_tool == button_index {
+                                gui.random_brush = !gui.random_brush;
+                            }
+                            mapview.clear_selection();
                             gui.set_tool(button_index);
+                            if gui.current_setting_tab != TAB_ATTRIBUTE {
+                                mapview.change_selection_preview_size(brush_preview_size(gui, tileset));
+                            }
+                            gui.update_tool_label(renderer);
+                        },
+                        TOOL_SELECT => {
+                            // Clicking on the selection tool while it is being used switch between the current layer and every layer
+                            if gui.current_tool == TOOL_SELECT {
+                                gui.select_all_layers = !gui.select_all_layers;
+                            }
+                            mapview.change_selection_preview_size(Vec2::new(1.0, 1.0));
+                            gui.set_tool(button_index);
+                            gui.update_tool_label(renderer);
+                        },
+                        TOOL_TERRAIN => {
+                            // Clicking on the terrain tool while it is being used switch to the next terrain
+                            if gui.current_tool == TOOL_TERRAIN && !gui.terrains.is_empty() {
+                                gui.current_terrain = (gui.current_terrain + 1) % gui.terrains.len();
+                            }
+                            // The terrain is painted one tile at a time
+                            mapview.change_selection_preview_size(Vec2::new(1.0, 1.0));
+                            mapview.clear_selection();
+                            gui.set_tool(button_index);
+                            gui.update_tool_label(renderer);
                         },
                         TAB_ATTRIBUTE | TAB_LAYER | TAB_PROPERTIES => {
                             gui.set_tab(button_index);
+                            // Attributes and terrains are always placed one tile at a time
+                            if button_index == TAB_ATTRIBUTE || gui.current_tool == TOOL_TERRAIN {
+                                mapview.change_selection_preview_size(Vec2::new(1.0, 1.0));
+                            } else {
+                                mapview.change_selection_preview_size(brush_preview_size(gui, tileset));
+                            }
                         },
                         BUTTON_TILESET => {
                             if gui.tileset_list.visible {
@@ -349,7 +1133,17 @@ pub fn handle_input(renderer: &mut GpuRenderer,
                 // Tab Options
                 let click_tab_option = gui.click_tab_option(screen_pos);
                 if click_tab_option.is_some() {
-                    gui.select_tab_option(click_tab_option.unwrap());
+                    gui.select_tab_option(renderer, click_tab_option.unwrap());
+                }
+                if gui.current_setting_tab == TAB_ATTRIBUTE {
+                    gui.attribute_setting.select_textbox(screen_pos);
+                }
+                if gui.current_setting_tab == TAB_PROPERTIES {
+                    if let Some(index) = gui.property_setting.select_box(screen_pos) {
+                        if gui.property_setting.next_choice(renderer, index) {
+                            apply_map_properties(renderer, gui, editor_data);
+                        }
+                    }
                 }
 
                 // Tileset List
@@ -371,20 +1165,52 @@ pub fn handle_input(renderer: &mut GpuRenderer,
                     if gameinput.tileset_end != tile_map_pos { 
                         gameinput.tileset_end = tile_map_pos;
                         gameinput.return_size = tileset.set_selection(gameinput.tileset_start, gameinput.tileset_end);
-                        mapview.change_selection_preview_size(gameinput.return_size);
+                        if gui.current_setting_tab != TAB_ATTRIBUTE {
+                            mapview.change_selection_preview_size(brush_preview_size(gui, tileset));
+                        }
                     }
                 }
 
                 // Check if mouse position is pointing to our map view
-                if in_map(screen_pos, mapview) && gameinput.presstype == PressType::PressMap {
+                if gameinput.presstype == PressType::PressMap && gui.current_tool == TOOL_SELECT {
+                    // The selection keeps following the mouse outside of the map but it stays within the map
+                    let tile_map_pos = get_map_pos(screen_pos, mapview);
+                    match gameinput.select_origin {
+                        Some(origin) => move_selection(renderer, resource, gui, mapview, editor_data, origin, tile_map_pos - gameinput.map_start),
+                        None => mapview.set_selection(gameinput.map_start, tile_map_pos),
+                    }
+                } else if in_map(screen_pos, mapview) && gameinput.presstype == PressType::PressMap {
                     // Calculate the tile position on the map based on mouse position
                     let tile_map_pos = get_map_pos(screen_pos, mapview);
 
                     gui.labels[LABEL_TILEPOS].set_text(renderer, &format!("Tile [ X: {} Y: {} ]", tile_map_pos.x, tile_map_pos.y), Attrs::new());
                     
-                    interact_with_map(renderer,tile_map_pos, gui, tileset, mapview, editor_data);
+                    if gui.current_tool == TOOL_EYEDROP && gui.current_setting_tab == TAB_ATTRIBUTE {
+                        gui.attribute_setting.set_attribute(renderer, mapview.document.get_attribute(tile_map_pos));
+                    } else if gui.current_tool == TOOL_EYEDROP {
+                        // Dragging will pick all the tiles within the area
+                        if let Some(size) = pick_map_tiles(renderer, resource, gui, tileset, mapview, gameinput.map_start, tile_map_pos) {
+                            gameinput.return_size = size;
+                        }
+                        mapview.hover_selection_preview(gameinput.map_start.min(tile_map_pos));
+                        mapview.change_selection_preview_size((gameinput.map_start - tile_map_pos).abs() + 1.0);
+                    } else if gui.current_tool == TOOL_SHAPE && gui.current_setting_tab == TAB_LAYER {
+                        interact_with_map(renderer, resource, gameinput.map_start, tile_map_pos, gui, tileset, mapview, editor_data);
+                        preview_shape_bounds(mapview, gameinput.map_start, tile_map_pos);
+                    } else {
+                        interact_with_map(renderer, resource, gameinput.map_start, tile_map_pos, gui, tileset, mapview, editor_data);
 
-                    mapview.hover_selection_preview(tile_map_pos);
+                        mapview.hover_selection_preview(tile_map_pos);
+                    }
+                } else if gameinput.presstype == PressType::PressMap
+                    && gui.current_setting_tab == TAB_LAYER && gui.current_tool != TOOL_EYEDROP {
+                    // A stroke that started on the main map continues on the linked map strips
+                    if let Some(tile_map_pos) = get_view_pos(screen_pos, mapview) {
+                        interact_with_map(renderer, resource, gameinput.map_start, tile_map_pos, gui, tileset, mapview, editor_data);
+                        if gui.current_tool == TOOL_SHAPE {
+                            preview_shape_bounds(mapview, gameinput.map_start, tile_map_pos);
+                        }
+                    }
                 }
             } else {
                 // Update our tileset list based on the scrollbar value
@@ -399,6 +1225,12 @@ pub fn handle_input(renderer: &mut GpuRenderer,
             // We check if we can create the effect if the linked map is being hover
             gameinput.selected_link_map = mapview.hover_linked_selection(screen_pos);
 
+            // The preview goes back to the brush size once the shape has been drawn
+            if gameinput.presstype == PressType::PressMap && gui.current_tool == TOOL_SHAPE && gui.current_setting_tab == TAB_LAYER {
+                gameinput.presstype = PressType::PressNone;
+                mapview.change_selection_preview_size(tileset.get_brush_size());
+            }
+
             // Calculate the tile position on the map based on mouse position
             if in_map(screen_pos, mapview) {
                 let tile_map_pos = get_map_pos(screen_pos, mapview);
@@ -419,15 +1251,21 @@ pub fn handle_input(renderer: &mut GpuRenderer,
 }
 
 pub fn handle_key_input(renderer: &mut GpuRenderer,
+                        resource: &TextureAllocation,
                         event: &KeyEvent,
-                        gui: &mut Interface,)
+                        gui: &mut Interface,
+                        editor_data: &mut EditorData,)
 {
     if !event.state.is_pressed() {
         return;
     }
 
-    if let Some(dialog) = &mut gui.dialog {
-        if dialog.dialog_type == DialogType::TypeMapLoad {
+    if gui.dialog.is_none() && gui.world_view.is_some() {
+        if event.logical_key == Key::Named(NamedKey::Escape) {
+            gui.world_view = None;
+        }
+    } else if let Some(dialog) = &mut gui.dialog {
+        if dialog.dialog_type == DialogType::TypeMapNew {
             if dialog.editing_index < 2 {
                 enter_numeric(&mut dialog.editor_data[dialog.editing_index], event, 5, true);
             } else {
@@ -435,5 +1273,48 @@ pub fn handle_key_input(renderer: &mut GpuRenderer,
             }
             dialog.update_editor_data(renderer);
         }
+    } else if gui.map_browser.is_some() {
+        if event.logical_key == Key::Named(NamedKey::Escape) {
+            gui.map_browser = None;
+        } else if let Some(map_browser) = &mut gui.map_browser {
+            if let Some(index) = map_browser.editing_index {
+                if index == FILTER_NAME {
+                    enter_text(&mut map_browser.filter_data[index], event, 30);
+                } else {
+                    enter_numeric(&mut map_browser.filter_data[index], event, 5, index != FILTER_GROUP);
+                }
+                map_browser.update_filter_text(renderer);
+                map_browser.apply_filter(resource, renderer);
+            }
+        }
+    } else if gui.stamp_browser.is_some() {
+        if event.logical_key == Key::Named(NamedKey::Escape) {
+            gui.stamp_browser = None;
+        } else if let Some(stamp_browser) = &mut gui.stamp_browser {
+            if stamp_browser.is_editing {
+                enter_text(&mut stamp_browser.name_data, event, 30);
+                stamp_browser.update_name_text(renderer);
+            }
+        }
+    } else if gui.current_setting_tab == TAB_ATTRIBUTE {
+        let setting = &mut gui.attribute_setting;
+        if let Some(index) = setting.editing_index {
+            // Only the warp map location can be negative
+            let is_warp = setting.selected == 1;
+            enter_numeric(&mut setting.field_data[index], event, if is_warp { 5 } else { 9 }, is_warp && index < 2);
+            setting.update_field_text(renderer);
+        }
+    } else if gui.current_setting_tab == TAB_PROPERTIES {
+        let setting = &mut gui.property_setting;
+        if let Some(index) = setting.editing_index {
+            let textbox = &mut setting.boxes[index];
+            match textbox.field_type {
+                FieldType::Text => enter_text(&mut textbox.data, event, textbox.limit),
+                FieldType::Numeric { can_be_negative } => enter_numeric(&mut textbox.data, event, textbox.limit, can_be_negative),
+                FieldType::Choice => {},
+            }
+            setting.update_box_text(renderer);
+            apply_map_properties(renderer, gui, editor_data);
+        }
     }
 }
\ No newline at end of file
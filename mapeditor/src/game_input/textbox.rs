@@ -12,6 +12,28 @@ pub fn is_numeric(char: &str) -> bool {
     char.trim().parse::<i64>().is_ok()
 }
 
+pub fn enter_text(text: &mut String,
+                    event: &KeyEvent,
+                    limit: usize,)
+{
+    if !event.state.is_pressed() {
+        return;
+    }
+
+    if event.physical_key == KeyCode::Backspace {
+        text.pop();
+    } else {
+        if text.chars().count() >= limit {
+            return;
+        }
+        if let Some(char) = event.logical_key.to_text() {
+            if !char.chars().any(|c| c.is_control()) {
+                text.push_str(char);
+            }
+        }
+    }
+}
+
 pub fn enter_numeric(text: &mut String,
                     event: &KeyEvent,
                     limit: usize,
@@ -3,37 +3,54 @@ use cosmic_text::{Attrs, Metrics};
 use winit::dpi::PhysicalSize;
 use crate::resource::*;
 use crate::collection::ZOOM_LEVEL;
+use crate::map_data::{ShapeType, TerrainSet, load_terrains, project_config};
 use indexmap::IndexMap;
+use log::warn;
 
 mod tabtext;
 mod tool;
 mod tileset_list;
 mod scrollbar;
+mod attribute_setting;
+mod property_setting;
+mod world_view;
+mod map_browser;
+mod stamp_browser;
 pub mod dialog;
 
 use tabtext::*;
 use tool::*;
 use tileset_list::*;
+pub use attribute_setting::*;
+pub use property_setting::*;
 pub use dialog::*;
+pub use world_view::*;
+pub use map_browser::*;
+pub use stamp_browser::*;
 
 pub const LABEL_FPS: usize = 0;
 pub const LABEL_TILESET: usize = 1;
 pub const LABEL_MAPNAME: usize = 2;
 pub const LABEL_TILEPOS: usize = 3;
+pub const LABEL_TOOL_OPTION: usize = 4;
 
 pub const TOOL_LOAD: usize = 0;
 pub const TOOL_SAVE: usize = 1;
 pub const TOOL_UNDO: usize = 2;
-pub const TOOL_DRAW: usize = 3;
-pub const TOOL_ERASE: usize = 4;
-pub const TOOL_FILL: usize = 5;
-pub const TOOL_EYEDROP: usize = 6;
-pub const TAB_LAYER: usize = 7;
-pub const TAB_ATTRIBUTE: usize = 8;
-pub const TAB_PROPERTIES: usize = 9;
-pub const BUTTON_TILESET: usize = 10;
-
-const MAX_TOOL: usize = 7;
+pub const TOOL_REDO: usize = 3;
+pub const TOOL_DRAW: usize = 4;
+pub const TOOL_ERASE: usize = 5;
+pub const TOOL_FILL: usize = 6;
+pub const TOOL_EYEDROP: usize = 7;
+pub const TOOL_SHAPE: usize = 8;
+pub const TOOL_SELECT: usize = 9;
+pub const TOOL_TERRAIN: usize = 10;
+pub const TAB_LAYER: usize = 11;
+pub const TAB_ATTRIBUTE: usize = 12;
+pub const TAB_PROPERTIES: usize = 13;
+pub const BUTTON_TILESET: usize = 14;
+
+const MAX_TOOL: usize = 11;
 const MAX_SETTING_TAB: usize = 3;
 const MAX_EXTRA_BUTTON: usize = 1;
 
@@ -42,12 +59,28 @@ pub struct Interface {
     pub labels: Vec<Text>,
     pub buttons: Vec<Tool>,
     pub current_tool: usize,
+    // Shape that is drawn by the shape tool
+    pub current_shape: ShapeType,
+    // The selection tool picks every layer and the attributes instead of the current layer
+    pub select_all_layers: bool,
+    // The draw and fill tools pick random tiles of the tileset selection instead of placing it as is
+    pub random_brush: bool,
+    // Seed of the stroke that is being painted with the random brush
+    pub brush_seed: u64,
+    // Terrains of the project, the terrain tool paints the current one
+    pub terrains: Vec<TerrainSet>,
+    pub current_terrain: usize,
     pub current_setting_tab: usize,
     reset_button: bool,
     pub tab_labels: Vec<TabText>,
     pub current_tab_data: u32,
+    pub attribute_setting: AttributeSetting,
+    pub property_setting: PropertySetting,
     pub tileset_list: TilesetList,
     pub dialog: Option<Dialog>,
+    pub world_view: Option<WorldView>,
+    pub map_browser: Option<MapBrowser>,
+    pub stamp_browser: Option<StampBrowser>,
 }
 
 impl Interface {
@@ -78,7 +111,11 @@ impl Interface {
             create_label(renderer, size, scale,
                 Vec3::new(810.0, 13.0, 10.0), 
                 Vec2::new(130.0, 20.0),
-                Color::rgba(180, 180, 180, 255)) // Tile Pos
+                Color::rgba(180, 180, 180, 255)), // Tile Pos
+            create_label(renderer, size, scale,
+                Vec3::new(590.0, 767.0, 10.0),
+                Vec2::new(200.0, 16.0),
+                Color::rgba(180, 180, 180, 255)) // Tool Option
         ];
 
         // Prepare Tools
@@ -97,7 +134,7 @@ impl Interface {
             tool.image.pos = Vec3::new(last_pos_x, 760.0, 10.0);
             match index {
                 TOOL_SAVE => { last_pos_x += 39.0; }
-                TOOL_UNDO => { last_pos_x += 39.0; }
+                TOOL_REDO => { last_pos_x += 39.0; }
                 _ => { last_pos_x += 32.0; }
             }
             tool.image.hw = Vec2::new(30.0, 30.0);
@@ -148,6 +185,12 @@ impl Interface {
                 Vec2::new(14.0, 298.0 - (21 * index) as f32)));
         }
 
+        // Attribute options and values
+        let attribute_setting = AttributeSetting::new(resource, renderer, size, scale);
+
+        // Map properties
+        let property_setting = PropertySetting::new(renderer, size, scale);
+
         // Tileset List
         let tileset_list = TilesetList::new(resource, renderer, size, scale);
 
@@ -159,6 +202,12 @@ impl Interface {
         labels[LABEL_TILESET].set_text(renderer, &resource.tilesheet[0].name, Attrs::new());
         labels[LABEL_MAPNAME].set_text(renderer, "Map [ X: 0 Y: 0 Group: 0 ]", Attrs::new());
         labels[LABEL_TILEPOS].set_text(renderer, "Tile [ X: 32 Y: 32 ]", Attrs::new());
+        labels[LABEL_TOOL_OPTION].set_text(renderer, "", Attrs::new());
+
+        let terrains = load_terrains(resource).unwrap_or_else(|e| {
+            warn!("Failed to read the terrains, Err {:?}", e);
+            Vec::new()
+        });
 
         // Completed! We can now pass the struct
         Self {
@@ -166,12 +215,23 @@ impl Interface {
             labels,
             buttons,
             current_tool: TOOL_DRAW,
+            current_shape: ShapeType::Rectangle,
+            select_all_layers: false,
+            random_brush: false,
+            brush_seed: 0,
+            terrains,
+            current_terrain: 0,
             current_setting_tab: TAB_LAYER,
             reset_button: false,
             tab_labels,
             current_tab_data: 0,
+            attribute_setting,
+            property_setting,
             tileset_list,
             dialog: None,
+            world_view: None,
+            map_browser: None,
+            stamp_browser: None,
         }
     }
 
@@ -220,6 +280,38 @@ impl Interface {
         }
     }
 
+    // Show the option of the current tool, such as the shape that is being drawn
+    pub fn update_tool_label(&mut self, renderer: &mut GpuRenderer) {
+        let msg = match self.current_tool {
+            TOOL_SHAPE => format!("Shape: {}", self.current_shape.as_str()),
+            TOOL_DRAW | TOOL_FILL if self.random_brush => match project_config().random_seed {
+                Some(seed) => format!("Random: Seed {}", seed),
+                None => "Random".to_string(),
+            },
+            TOOL_SELECT if self.select_all_layers => "Select: All Layers".to_string(),
+            TOOL_SELECT => "Select: Current Layer".to_string(),
+            TOOL_TERRAIN => match self.get_terrain() {
+                Some(terrain) => format!("Terrain: {}", terrain.name),
+                None => "Terrain: None".to_string(),
+            },
+            _ => String::new(),
+        };
+        self.labels[LABEL_TOOL_OPTION].set_text(renderer, &msg, Attrs::new());
+    }
+
+    pub fn get_terrain(&self) -> Option<&TerrainSet> {
+        self.terrains.get(self.current_terrain)
+    }
+
+    // The layers that are picked by the selection tool
+    pub fn selection_layers(&self) -> Vec<u32> {
+        if self.select_all_layers {
+            (0..MapLayers::Count as u32).collect()
+        } else {
+            vec![self.current_tab_data]
+        }
+    }
+
     // This function help us switch the map setting tab that the editor is using
     pub fn set_tab(&mut self, tab_index: usize) {
         if self.current_setting_tab != tab_index {
@@ -233,8 +325,12 @@ impl Interface {
                         self.tab_labels[index].text.changed = true;
                     }
                 },
-                TAB_ATTRIBUTE => {},
-                TAB_PROPERTIES => {},
+                TAB_ATTRIBUTE => {
+                    self.attribute_setting.set_changed();
+                },
+                TAB_PROPERTIES => {
+                    self.property_setting.set_changed();
+                },
                 _ => {},
             }
         }
@@ -256,7 +352,9 @@ impl Interface {
                     }
                 }
             },
-            TAB_ATTRIBUTE => {},
+            TAB_ATTRIBUTE => {
+                self.attribute_setting.hover_option(mouse_pos);
+            },
             TAB_PROPERTIES => {},
             _ => {},
         }
@@ -278,24 +376,26 @@ impl Interface {
                     .map(|(index, _)| index)
                     .next()
             },
-            TAB_ATTRIBUTE => { None },
+            TAB_ATTRIBUTE => { self.attribute_setting.click_option(mouse_pos) },
             TAB_PROPERTIES => { None },
             _ => { None },
         }
     }
 
-    pub fn select_tab_option(&mut self, tab_index: usize) {
-        if self.current_tab_data != tab_index as u32 {
-            match self.current_setting_tab {
-                TAB_LAYER => {
+    pub fn select_tab_option(&mut self, renderer: &mut GpuRenderer, tab_index: usize) {
+        match self.current_setting_tab {
+            TAB_LAYER => {
+                if self.current_tab_data != tab_index as u32 {
                     self.tab_labels[self.current_tab_data as usize].set_select(false);
                     self.tab_labels[tab_index].set_select(true);
                     self.current_tab_data = tab_index as u32;
                 }
-                TAB_ATTRIBUTE => {},
-                TAB_PROPERTIES => {},
-                _ => {},
             }
+            TAB_ATTRIBUTE => {
+                self.attribute_setting.select_option(renderer, tab_index);
+            },
+            TAB_PROPERTIES => {},
+            _ => {},
         }
     }
 
@@ -304,7 +404,7 @@ impl Interface {
     pub fn get_tab_option_data(&mut self) -> u32 {
         match self.current_setting_tab {
             TAB_LAYER => { self.current_tab_data as u32 }
-            TAB_ATTRIBUTE => { 0 as u32 },
+            TAB_ATTRIBUTE => { self.attribute_setting.selected as u32 },
             TAB_PROPERTIES => { 0 as u32 },
             _ => { 0 as u32 },
         }
@@ -317,6 +417,15 @@ impl Interface {
         self.dialog = Some(Dialog::new(resource, renderer, size, scale, dialogtype, data));
     }
 
+    pub fn open_error_dialog(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer, size: &PhysicalSize<f32>, scale: f64, msg: &str) {
+        if self.dialog.is_some() {
+            return;
+        }
+        let mut dialog = Dialog::new(resource, renderer, size, scale, DialogType::TypeError, None);
+        dialog.set_message(renderer, msg);
+        self.dialog = Some(dialog);
+    }
+
     pub fn close_dialog(&mut self) {
         self.dialog = None;
     }
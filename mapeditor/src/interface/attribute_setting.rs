@@ -0,0 +1,167 @@
+use graphics::*;
+use cosmic_text::Attrs;
+use winit::dpi::PhysicalSize;
+use crate::resource::*;
+use crate::map_data::*;
+use crate::interface::{create_label, tabtext::*};
+
+// The highest amount of values that an attribute can have
+const MAX_FIELD: usize = 5;
+
+pub struct AttributeSetting {
+    pub options: Vec<TabText>,
+    pub selected: usize,
+    pub field_labels: Vec<Text>,
+    pub field_boxes: Vec<Rect>,
+    pub field_texts: Vec<Text>,
+    pub field_data: Vec<String>,
+    pub editing_index: Option<usize>,
+}
+
+impl AttributeSetting {
+    pub fn new(resource: &TextureAllocation, renderer: &mut GpuRenderer, size: &PhysicalSize<f32>, scale: f64) -> Self {
+        let mut options = Vec::with_capacity(MAX_ATTRIBUTE);
+        for index in 0..MAX_ATTRIBUTE {
+            options.push(TabText::new(resource, renderer, size, scale,
+                MapAttribute::as_str(index),
+                Vec2::new(14.0, 298.0 - (21 * index) as f32)));
+        }
+        options[0].set_select(true); // Set Blocked as selected
+
+        // Each value will have a label on the left and a textbox on the right
+        let mut field_labels = Vec::with_capacity(MAX_FIELD);
+        let mut field_boxes = Vec::with_capacity(MAX_FIELD);
+        let mut field_texts = Vec::with_capacity(MAX_FIELD);
+        for index in 0..MAX_FIELD {
+            let pos_y = 184.0 - (28 * index) as f32;
+            field_labels.push(create_label(renderer, size, scale,
+                Vec3::new(16.0, pos_y, 1.9),
+                Vec2::new(90.0, 20.0),
+                Color::rgba(120, 120, 120, 255)));
+
+            let mut textbox = Rect::new(renderer, 0);
+            textbox.set_size(Vec2::new(96.0, 24.0))
+                .set_position(Vec3::new(110.0, pos_y - 2.0, 9.0))
+                .set_border_color(Color::rgba(80, 80, 80, 255))
+                .set_border_width(1.0)
+                .set_color(Color::rgba(80, 80, 80, 255));
+            field_boxes.push(textbox);
+
+            field_texts.push(create_label(renderer, size, scale,
+                Vec3::new(114.0, pos_y, 1.9),
+                Vec2::new(88.0, 20.0),
+                Color::rgba(200, 200, 200, 255)));
+        }
+
+        Self {
+            options,
+            selected: 0,
+            field_labels,
+            field_boxes,
+            field_texts,
+            field_data: vec![String::new(); MAX_FIELD],
+            editing_index: None,
+        }
+    }
+
+    // The amount of values that the selected attribute needs
+    pub fn field_count(&self) -> usize {
+        MapAttribute::field_names(self.selected).len()
+    }
+
+    // We need to update the render data whenever the tab is being shown again
+    pub fn set_changed(&mut self) {
+        self.options.iter_mut().for_each(|option| {
+            option.button.changed = true;
+            option.text.changed = true;
+        });
+        self.field_labels.iter_mut().for_each(|text| text.changed = true);
+        self.field_texts.iter_mut().for_each(|text| text.changed = true);
+        self.field_boxes.iter_mut().for_each(|textbox| textbox.changed = true);
+    }
+
+    pub fn hover_option(&mut self, mouse_pos: Vec2) {
+        self.options.iter_mut().for_each(|option| {
+            let is_within_pos = mouse_pos.x >= option.button.pos.x
+                && mouse_pos.x <= option.button.pos.x + option.button.hw.x
+                && mouse_pos.y >= option.button.pos.y
+                && mouse_pos.y <= option.button.pos.y + option.button.hw.y;
+            option.set_hover(is_within_pos);
+        });
+    }
+
+    pub fn click_option(&mut self, mouse_pos: Vec2) -> Option<usize> {
+        self.options.iter().position(|option| {
+            mouse_pos.x >= option.button.pos.x
+                && mouse_pos.x <= option.button.pos.x + option.button.hw.x
+                && mouse_pos.y >= option.button.pos.y
+                && mouse_pos.y <= option.button.pos.y + option.button.hw.y
+        })
+    }
+
+    pub fn select_option(&mut self, renderer: &mut GpuRenderer, index: usize) {
+        if self.selected == index {
+            return;
+        }
+        self.options[self.selected].set_select(false);
+        self.options[index].set_select(true);
+        self.selected = index;
+
+        // The values are cleared as each attribute have different values
+        let field_names = MapAttribute::field_names(index);
+        for field in 0..MAX_FIELD {
+            self.field_labels[field].set_text(renderer, field_names.get(field).copied().unwrap_or_default(), Attrs::new());
+            self.field_data[field].clear();
+            self.field_texts[field].set_text(renderer, "", Attrs::new());
+        }
+        self.select_textbox(Vec2::new(-1.0, -1.0));
+    }
+
+    // This is used by the eyedropper so that the picked attribute can be placed again
+    pub fn set_attribute(&mut self, renderer: &mut GpuRenderer, attribute: MapAttribute) {
+        let Some(index) = attribute.get_index() else {
+            return;
+        };
+        self.select_option(renderer, index);
+
+        let values = match attribute {
+            MapAttribute::Warp(data) => vec![data.map_x as i64, data.map_y as i64, data.map_group as i64, data.tile_x as i64, data.tile_y as i64],
+            MapAttribute::Item { id, quantity } => vec![id as i64, quantity as i64],
+            MapAttribute::NpcSpawn { id } => vec![id as i64],
+            MapAttribute::Door { key } => vec![key as i64],
+            _ => vec![],
+        };
+        for (field, value) in values.iter().enumerate() {
+            self.field_data[field] = value.to_string();
+            self.field_texts[field].set_text(renderer, &self.field_data[field], Attrs::new());
+        }
+    }
+
+    pub fn select_textbox(&mut self, mouse_pos: Vec2) {
+        let field_count = self.field_count();
+        self.editing_index = None;
+        for (index, textbox) in self.field_boxes.iter_mut().enumerate() {
+            if index < field_count
+                && mouse_pos.x >= textbox.position.x
+                && mouse_pos.x <= textbox.position.x + textbox.size.x
+                && mouse_pos.y >= textbox.position.y
+                && mouse_pos.y <= textbox.position.y + textbox.size.y
+            {
+                textbox.set_border_color(Color::rgba(180, 180, 180, 255));
+                self.editing_index = Some(index);
+            } else {
+                textbox.set_border_color(Color::rgba(80, 80, 80, 255));
+            }
+        }
+    }
+
+    pub fn update_field_text(&mut self, renderer: &mut GpuRenderer) {
+        if let Some(index) = self.editing_index {
+            self.field_texts[index].set_text(renderer, &self.field_data[index], Attrs::new());
+        }
+    }
+
+    pub fn get_attribute(&self) -> MapAttribute {
+        MapAttribute::from_fields(self.selected, &self.field_data)
+    }
+}
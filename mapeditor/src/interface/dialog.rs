@@ -11,7 +11,10 @@ pub enum DialogType {
     TypeNone,
     TypeExitConfirm,
     TypeMapSave,
-    TypeMapLoad,
+    TypeMapNew,
+    TypeMapRecovery,
+    TypeMapMigrated,
+    TypeError,
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -20,6 +23,7 @@ pub enum DialogButtonType {
     ButtonConfirm,
     ButtonDecline,
     ButtonCancel,
+    ButtonDelete,
 }
 
 #[derive(Debug)]
@@ -148,12 +152,17 @@ impl Dialog {
             match dialog_type {
                 DialogType::TypeExitConfirm => 384.0,
                 DialogType::TypeMapSave => 456.0,
-                DialogType::TypeMapLoad => 456.0,
+                DialogType::TypeMapNew => 456.0,
+                DialogType::TypeMapRecovery => 456.0,
+                DialogType::TypeMapMigrated => 456.0,
+                DialogType::TypeError => 456.0,
                 _ => { 384.0 },
             }, match dialog_type {
                 DialogType::TypeExitConfirm => 108.0,
                 DialogType::TypeMapSave => 201.0,
-                DialogType::TypeMapLoad => 144.0,
+                DialogType::TypeMapNew => 144.0,
+                DialogType::TypeMapRecovery => 201.0,
+                DialogType::TypeMapMigrated => 201.0,
                 _ => { 108.0 },
             });
         window_pos = Vec2::new((size.width * 0.5) - ((window_size.x * 0.5) * ZOOM_LEVEL),
@@ -161,13 +170,19 @@ impl Dialog {
         message_pos_y = match dialog_type {
             DialogType::TypeExitConfirm => window_pos.y + 62.0,
             DialogType::TypeMapSave => window_pos.y + 155.0,
-            DialogType::TypeMapLoad => window_pos.y + 98.0,
+            DialogType::TypeMapNew => window_pos.y + 98.0,
+            DialogType::TypeMapRecovery => window_pos.y + 155.0,
+            DialogType::TypeMapMigrated => window_pos.y + 155.0,
+            DialogType::TypeError => window_pos.y + 62.0,
             _ => { 62.0 },
         };
         button_pos = Vec2::new(match dialog_type {
             DialogType::TypeExitConfirm => window_pos.x + 84.0,
-            DialogType::TypeMapLoad => window_pos.x + 120.0,
+            DialogType::TypeMapNew => window_pos.x + 120.0,
             DialogType::TypeMapSave => window_pos.x + 64.0,
+            DialogType::TypeMapRecovery => window_pos.x + 120.0,
+            DialogType::TypeMapMigrated => window_pos.x + 176.0,
+            DialogType::TypeError => window_pos.x + 176.0,
             _ => { window_pos.x + 84.0 },
         }, window_pos.y + 18.0);
 
@@ -186,12 +201,23 @@ impl Dialog {
                     DialogButton::new(resource, renderer, size, scale, "Cancel", button_pos + Vec2::new(226.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonCancel),
                 ]
             }
-            DialogType::TypeMapLoad => {
+            DialogType::TypeMapNew => {
                 vec![
-                    DialogButton::new(resource, renderer, size, scale, "Load", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonConfirm),
+                    DialogButton::new(resource, renderer, size, scale, "Create", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonConfirm),
                     DialogButton::new(resource, renderer, size, scale, "Cancel", button_pos + Vec2::new(113.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonCancel),
                 ]
             }
+            DialogType::TypeMapRecovery => {
+                vec![
+                    DialogButton::new(resource, renderer, size, scale, "Restore", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonConfirm),
+                    DialogButton::new(resource, renderer, size, scale, "Discard", button_pos + Vec2::new(113.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonDecline),
+                ]
+            }
+            DialogType::TypeMapMigrated | DialogType::TypeError => {
+                vec![
+                    DialogButton::new(resource, renderer, size, scale, "Okay", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonCancel),
+                ]
+            }
             _ => {vec![]}
         };
 
@@ -207,7 +233,9 @@ impl Dialog {
         let msg = match dialog_type {
             DialogType::TypeExitConfirm => "Are you sure that you want to close the editor?",
             DialogType::TypeMapSave => "Would you like to save the changes to the following map/s?",
-            DialogType::TypeMapLoad => "Please enter the map location that you would like to load",
+            DialogType::TypeMapNew => "Please enter the location of the new map",
+            DialogType::TypeMapRecovery => "Unsaved changes were recovered for the following map/s",
+            DialogType::TypeMapMigrated => "The following map/s were upgraded to the current format",
             _ => "Error",
         };
 
@@ -225,7 +253,7 @@ impl Dialog {
 
         // Stored Data
         let editor_data = match dialog_type {
-            DialogType::TypeMapSave => {
+            DialogType::TypeMapSave | DialogType::TypeMapRecovery | DialogType::TypeMapMigrated => {
                 let list_data = data.unwrap();
                 let mut text_data = Vec::with_capacity(list_data.len());
                 for (key, value) in list_data.iter() {
@@ -235,7 +263,7 @@ impl Dialog {
                 }
                 text_data
             },
-            DialogType::TypeMapLoad => {
+            DialogType::TypeMapNew => {
                 vec![String::new(); 3]
             },
             _ => { Vec::with_capacity(0) },
@@ -244,7 +272,7 @@ impl Dialog {
         // Content
         let mut scrollbar_x = window_pos.x;
         let content_image = match dialog_type {
-            DialogType::TypeMapSave => {
+            DialogType::TypeMapSave | DialogType::TypeMapRecovery | DialogType::TypeMapMigrated => {
                 let label_box_size = Vec2::new(364.0, 85.0);
                 let label_box_pos = Vec2::new(window_pos.x + ((window_size.x * 0.5) - (label_box_size.x * 0.5)).floor(), window_pos.y + 65.0);
                 scrollbar_x = label_box_pos.x;
@@ -259,7 +287,7 @@ impl Dialog {
                         .set_color(Color::rgba(40, 40, 40, 255));
                 vec![label_box, scrollbar_box]
             }
-            DialogType::TypeMapLoad => {
+            DialogType::TypeMapNew => {
                 // Text Size = X[10] Y[10] Group[45]
                 let textbox_total_size = 240.0; // [10][50][5][10][50][5][45][50]
                 let content_pos = Vec2::new(window_pos.x + ((window_size.x * 0.5) - (textbox_total_size * 0.5)), window_pos.y + 66.0).floor();
@@ -286,7 +314,7 @@ impl Dialog {
             _ => { Vec::with_capacity(0) },
         };
         let content_text = match dialog_type {
-            DialogType::TypeMapSave => {
+            DialogType::TypeMapSave | DialogType::TypeMapRecovery | DialogType::TypeMapMigrated => {
                 let mut data = Vec::with_capacity(4);
                 for index in 0..4 {
                     let label_size = Vec2::new(362.0, 20.0);
@@ -305,7 +333,7 @@ impl Dialog {
                 }
                 data
             },
-            DialogType::TypeMapLoad => {
+            DialogType::TypeMapNew => {
                 // Text Size = X[10] Y[10] Group[45]
                 let textbox_total_size = 240.0; // [10][5][50][5][10][5][50][5][45][5][50]
                 let content_pos = Vec2::new(window_pos.x + ((window_size.x * 0.5) - (textbox_total_size * 0.5)), window_pos.y + 66.0).floor();
@@ -333,7 +361,7 @@ impl Dialog {
         };
         let editor_text = match dialog_type {
             DialogType::TypeMapSave => { Vec::with_capacity(0) },
-            DialogType::TypeMapLoad => {
+            DialogType::TypeMapNew => {
                 // Text Size = X[10] Y[10] Group[45]
                 let textbox_total_size = 240.0; // [10][5][50][5][10][5][50][5][45][5][50]
                 let content_pos = Vec2::new(window_pos.x + ((window_size.x * 0.5) - (textbox_total_size * 0.5)), window_pos.y + 66.0).floor();
@@ -362,7 +390,7 @@ impl Dialog {
 
         // Handle Scrollbar data
         let mut scrollbar_amount = 0;
-        if dialog_type == DialogType::TypeMapSave && editor_data.len() > 4 {
+        if matches!(dialog_type, DialogType::TypeMapSave | DialogType::TypeMapRecovery | DialogType::TypeMapMigrated) && editor_data.len() > 4 {
             scrollbar_amount = editor_data.len() - 4;
         }
         let scrollbar = Scrollbar::new(resource, renderer, 
@@ -387,6 +415,11 @@ impl Dialog {
         }
     }
 
+    // Dialogs that show a scrollable list of maps
+    pub fn is_list(&self) -> bool {
+        matches!(self.dialog_type, DialogType::TypeMapSave | DialogType::TypeMapRecovery | DialogType::TypeMapMigrated)
+    }
+
     pub fn hover_buttons(&mut self, mouse_pos: Vec2) {
         self.buttons.iter_mut().for_each(|button| {
             if (mouse_pos.x) >= button.image.pos.x
@@ -427,15 +460,23 @@ impl Dialog {
         button_type
     }
 
+    // Replace the message of the dialog, this is used by dialogs that does not have a fixed message
+    pub fn set_message(&mut self, renderer: &mut GpuRenderer, msg: &str) {
+        self.message.set_text(renderer, msg, Attrs::new());
+        let message_size = self.message.measure();
+        self.message.pos.x = self.window.position.x + ((self.window.size.x * 0.5) - (message_size.x * 0.5)).floor();
+        self.message.changed = true;
+    }
+
     pub fn update_editor_data(&mut self, renderer: &mut GpuRenderer) {
-        if self.dialog_type != DialogType::TypeMapLoad {
+        if self.dialog_type != DialogType::TypeMapNew {
             return;
         }
         self.editor_text[self.editing_index].set_text(renderer, &self.editor_data[self.editing_index], Attrs::new());
     }
 
     pub fn select_text(&mut self, mouse_pos: Vec2) {
-        if self.dialog_type != DialogType::TypeMapLoad {
+        if self.dialog_type != DialogType::TypeMapNew {
             return;
         }
 
@@ -0,0 +1,405 @@
+use graphics::*;
+use cosmic_text::Attrs;
+use winit::dpi::PhysicalSize;
+use log::warn;
+use crate::resource::*;
+use crate::collection::ZOOM_LEVEL;
+use crate::map_data::*;
+use crate::interface::{
+    create_label,
+    dialog::*,
+    scrollbar::*,
+    upload_map_thumbnail,
+};
+
+const MAX_VISIBLE_ROW: usize = 8;
+const ROW_HEIGHT: f32 = 42.0;
+
+pub const FILTER_X: usize = 0;
+pub const FILTER_Y: usize = 1;
+pub const FILTER_GROUP: usize = 2;
+pub const FILTER_NAME: usize = 3;
+
+pub struct MapEntry {
+    pub x: i32,
+    pub y: i32,
+    pub group: u64,
+    pub name: String,
+    texture: Option<usize>,
+    is_unsaved: bool,
+}
+
+impl MapEntry {
+    fn label(&self) -> String {
+        let mut label = format!("X: {} Y: {} Group: {}", self.x, self.y, self.group);
+        if !self.name.is_empty() {
+            label.push_str(&format!("  {}", self.name));
+        }
+        if self.is_unsaved {
+            label.push_str("  (Unsaved)");
+        }
+        label
+    }
+}
+
+// List of the maps that can be opened, the list can be filtered by location and map name
+pub struct MapBrowser {
+    pub bg: Image,
+    pub window: Rect,
+    pub message: Text,
+    pub count_label: Text,
+    pub filter_labels: Vec<Text>,
+    pub filter_boxes: Vec<Rect>,
+    pub filter_texts: Vec<Text>,
+    pub filter_data: Vec<String>,
+    pub editing_index: Option<usize>,
+    pub list_box: Vec<Rect>,
+    pub rows: Vec<Rect>,
+    pub thumbnails: Vec<Image>,
+    pub row_texts: Vec<Text>,
+    pub buttons: Vec<DialogButton>,
+    pub scrollbar: Scrollbar,
+    entries: Vec<MapEntry>,
+    // Index of the entries that matches the filter
+    filtered: Vec<usize>,
+    start_view_index: usize,
+    selected: Option<usize>,
+    hover_row: Option<usize>,
+    did_click: bool,
+    scrollbar_pos: Vec3,
+}
+
+impl MapBrowser {
+    pub fn new(resource: &TextureAllocation,
+                renderer: &mut GpuRenderer,
+                atlas: &mut AtlasSet,
+                size: &PhysicalSize<f32>,
+                scale: f64,
+                editor_data: &EditorData) -> Self {
+        // This image is for the transparent shadow that will render behind the map browser
+        let mut bg = Image::new(Some(resource.white.allocation), renderer, 1);
+        bg.pos = Vec3::new(0.0, 0.0, 0.9);
+        bg.hw = Vec2::new(size.width, size.height);
+        bg.uv = Vec4::new(2.0, 2.0, 17.0, 17.0);
+        bg.color = Color::rgba(0, 0, 0, 200);
+
+        let window_size = Vec2::new(456.0, 520.0);
+        let window_pos = Vec2::new((size.width * 0.5) - ((window_size.x * 0.5) * ZOOM_LEVEL),
+                            (size.height * 0.5) - ((window_size.y * 0.5) * ZOOM_LEVEL)).floor();
+        let mut window = Rect::new(renderer, 0);
+        window.set_size(window_size)
+            .set_position(Vec3::new(window_pos.x, window_pos.y, 0.8))
+            .set_radius(3.0)
+            .set_border_color(Color::rgba(10, 10, 10, 255))
+            .set_border_width(2.0)
+            .set_color(Color::rgba(50, 50, 50, 255));
+
+        let mut message = create_label(renderer, size, scale,
+            Vec3::new(window_pos.x, window_pos.y + 486.0, 0.6),
+            Vec2::new(window_size.x, 20.0),
+            Color::rgba(120, 120, 120, 255));
+        message.set_text(renderer, "Please select the map that you would like to open", Attrs::new());
+        let message_size = message.measure();
+        message.pos.x = window_pos.x + ((window_size.x * 0.5) - (message_size.x * 0.5)).floor();
+        message.changed = true;
+
+        // Filters, X[15][50] Y[15][50] Group[45][50] Name[45][120]
+        let content_x = window_pos.x + 18.0;
+        let filter_y = window_pos.y + 446.0;
+        let mut filter_labels = Vec::with_capacity(4);
+        let mut filter_boxes = Vec::with_capacity(4);
+        let mut filter_texts = Vec::with_capacity(4);
+        for (label, label_x, box_x, box_width) in [
+            ("X", 0.0, 15.0, 50.0),
+            ("Y", 75.0, 90.0, 50.0),
+            ("Group", 150.0, 195.0, 50.0),
+            ("Name", 255.0, 300.0, 120.0),
+        ] {
+            let mut text = create_label(renderer, size, scale,
+                Vec3::new(content_x + label_x, filter_y, 0.6),
+                Vec2::new(box_x - label_x, 20.0),
+                Color::rgba(120, 120, 120, 255));
+            text.set_text(renderer, label, Attrs::new());
+            filter_labels.push(text);
+
+            let mut textbox = Rect::new(renderer, 0);
+            textbox.set_size(Vec2::new(box_width, 24.0))
+                .set_position(Vec3::new(content_x + box_x, filter_y, 0.7))
+                .set_border_color(Color::rgba(80, 80, 80, 255))
+                .set_border_width(1.0)
+                .set_color(Color::rgba(80, 80, 80, 255));
+            filter_boxes.push(textbox);
+
+            let mut text = create_label(renderer, size, scale,
+                Vec3::new(content_x + box_x + 2.0, filter_y, 0.6),
+                Vec2::new(box_width - 4.0, 20.0),
+                Color::rgba(200, 200, 200, 255));
+            text.set_text(renderer, "", Attrs::new());
+            filter_texts.push(text);
+        }
+
+        // List
+        let list_pos = Vec2::new(content_x, window_pos.y + 66.0);
+        let list_size = Vec2::new(420.0, 340.0);
+        let mut label_box = Rect::new(renderer, 0);
+        label_box.set_size(list_size)
+            .set_position(Vec3::new(list_pos.x, list_pos.y, 0.78))
+            .set_color(Color::rgba(60, 60, 60, 255));
+        let mut scrollbar_box = Rect::new(renderer, 0);
+        scrollbar_box.set_size(Vec2::new(8.0, list_size.y - 4.0))
+            .set_position(Vec3::new(list_pos.x + 410.0, list_pos.y + 2.0, 0.7))
+            .set_color(Color::rgba(40, 40, 40, 255));
+        let list_box = vec![label_box, scrollbar_box];
+
+        let mut rows = Vec::with_capacity(MAX_VISIBLE_ROW);
+        let mut thumbnails = Vec::with_capacity(MAX_VISIBLE_ROW);
+        let mut row_texts = Vec::with_capacity(MAX_VISIBLE_ROW);
+        for index in 0..MAX_VISIBLE_ROW {
+            let row_y = list_pos.y + list_size.y - (ROW_HEIGHT * (index + 1) as f32);
+            let mut row = Rect::new(renderer, 0);
+            row.set_size(Vec2::new(404.0, ROW_HEIGHT - 2.0))
+                .set_position(Vec3::new(list_pos.x + 2.0, row_y, 0.75))
+                .set_color(Color::rgba(60, 60, 60, 255));
+            rows.push(row);
+
+            let mut image = Image::new(None, renderer, 1);
+            image.pos = Vec3::new(list_pos.x + 4.0, row_y + 1.0, 0.7);
+            image.hw = Vec2::new(36.0, 36.0);
+            image.uv = Vec4::new(0.0, 0.0, 32.0, 32.0);
+            image.color = Color::rgba(255, 255, 255, 255);
+            thumbnails.push(image);
+
+            let mut text = create_label(renderer, size, scale,
+                Vec3::new(list_pos.x + 48.0, row_y + 10.0, 0.6),
+                Vec2::new(354.0, 20.0),
+                Color::rgba(180, 180, 180, 255));
+            text.set_text(renderer, "", Attrs::new());
+            row_texts.push(text);
+        }
+
+        let mut count_label = create_label(renderer, size, scale,
+            Vec3::new(list_pos.x, list_pos.y + list_size.y + 8.0, 0.6),
+            Vec2::new(list_size.x, 20.0),
+            Color::rgba(120, 120, 120, 255));
+        count_label.set_text(renderer, "", Attrs::new());
+
+        let button_pos = Vec2::new(window_pos.x + 64.0, window_pos.y + 18.0);
+        let buttons = vec![
+            DialogButton::new(resource, renderer, size, scale, "Open", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonConfirm),
+            DialogButton::new(resource, renderer, size, scale, "New Map", button_pos + Vec2::new(113.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonDecline),
+            DialogButton::new(resource, renderer, size, scale, "Cancel", button_pos + Vec2::new(226.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonCancel),
+        ];
+
+        // Maps
+        let colors = TileColors::load().unwrap_or_else(|e| {
+            warn!("Failed to read the tilesheets, Err {:?}", e);
+            TileColors::default()
+        });
+        let mut entries = Vec::new();
+        for (x, y, group) in editor_data.list_maps() {
+            let key = format!("{}_{}_{}", x, y, group);
+            let data = match editor_data.peek_map(x, y, group) {
+                Ok(Some(data)) => data,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read map {}, Err {:?}", key, e);
+                    continue;
+                }
+            };
+            let texture = upload_map_thumbnail(renderer, atlas, &key, &data, &colors);
+            entries.push(MapEntry {
+                x,
+                y,
+                group,
+                name: data.properties.name,
+                texture,
+                is_unsaved: editor_data.did_map_change.get(&key).copied().unwrap_or_default(),
+            });
+        }
+
+        let scrollbar_pos = Vec3::new(list_pos.x + 409.0, list_pos.y + list_size.y - 5.0, 0.5);
+        let scrollbar = Scrollbar::new(resource, renderer, scrollbar_pos, 0, 330, 20);
+
+        let mut map_browser = Self {
+            bg,
+            window,
+            message,
+            count_label,
+            filter_labels,
+            filter_boxes,
+            filter_texts,
+            filter_data: vec![String::new(); 4],
+            editing_index: None,
+            list_box,
+            rows,
+            thumbnails,
+            row_texts,
+            buttons,
+            scrollbar,
+            entries,
+            filtered: Vec::new(),
+            start_view_index: 0,
+            selected: None,
+            hover_row: None,
+            did_click: false,
+            scrollbar_pos,
+        };
+        map_browser.apply_filter(resource, renderer);
+        map_browser
+    }
+
+    fn matches_filter(&self, entry: &MapEntry) -> bool {
+        let numeric = |index: usize, value: i64| {
+            let filter = self.filter_data[index].trim();
+            filter.is_empty() || filter == "-" || filter.parse::<i64>().is_ok_and(|filter| filter == value)
+        };
+        let name = self.filter_data[FILTER_NAME].trim().to_lowercase();
+        numeric(FILTER_X, entry.x as i64)
+            && numeric(FILTER_Y, entry.y as i64)
+            && numeric(FILTER_GROUP, entry.group as i64)
+            && (name.is_empty() || entry.name.to_lowercase().contains(&name))
+    }
+
+    // Rebuild the list with the maps that matches the filter, the scrollbar is recreated as the list size has changed
+    pub fn apply_filter(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer) {
+        self.filtered = self.entries.iter().enumerate()
+            .filter(|(_, entry)| self.matches_filter(entry))
+            .map(|(index, _)| index)
+            .collect();
+        if self.selected.is_some_and(|selected| !self.filtered.contains(&selected)) {
+            self.selected = None;
+        }
+
+        let scrollbar_amount = self.filtered.len().max(MAX_VISIBLE_ROW) - MAX_VISIBLE_ROW;
+        self.scrollbar = Scrollbar::new(resource, renderer, self.scrollbar_pos, scrollbar_amount, 330, 20);
+        self.start_view_index = 0;
+
+        self.count_label.set_text(renderer, &format!("{} of {} map/s", self.filtered.len(), self.entries.len()), Attrs::new());
+        self.update_list(renderer);
+    }
+
+    pub fn update_list(&mut self, renderer: &mut GpuRenderer) {
+        for index in 0..MAX_VISIBLE_ROW {
+            let entry_index = self.filtered.get(index + self.start_view_index).copied();
+            match entry_index {
+                Some(entry_index) => {
+                    let entry = &self.entries[entry_index];
+                    self.row_texts[index].set_text(renderer, &entry.label(), Attrs::new());
+                    self.thumbnails[index].texture = entry.texture;
+                    self.thumbnails[index].changed = true;
+                },
+                None => {
+                    self.row_texts[index].set_text(renderer, "", Attrs::new());
+                },
+            }
+            let color = if entry_index.is_some() && entry_index == self.selected {
+                Color::rgba(95, 95, 95, 255)
+            } else if entry_index.is_some() && Some(index) == self.hover_row {
+                Color::rgba(75, 75, 75, 255)
+            } else {
+                Color::rgba(60, 60, 60, 255)
+            };
+            self.rows[index].set_color(color);
+        }
+    }
+
+    pub fn update_scroll(&mut self, scroll_index: usize) -> bool {
+        if self.start_view_index != scroll_index {
+            self.start_view_index = scroll_index;
+            return true;
+        }
+        false
+    }
+
+    // Amount of rows that has a map on it, only these rows are rendered
+    pub fn visible_rows(&self) -> usize {
+        self.filtered.len().saturating_sub(self.start_view_index).min(MAX_VISIBLE_ROW)
+    }
+
+    fn find_row(&self, mouse_pos: Vec2) -> Option<usize> {
+        self.rows.iter().take(self.visible_rows()).position(|row| {
+            mouse_pos.x >= row.position.x
+                && mouse_pos.x <= row.position.x + row.size.x
+                && mouse_pos.y >= row.position.y
+                && mouse_pos.y <= row.position.y + row.size.y
+        })
+    }
+
+    // Returns the location of the selected map
+    pub fn get_selected(&self) -> Option<(i32, i32, u64)> {
+        let entry = &self.entries[self.selected?];
+        Some((entry.x, entry.y, entry.group))
+    }
+
+    pub fn select_row(&mut self, renderer: &mut GpuRenderer, mouse_pos: Vec2) {
+        if let Some(index) = self.find_row(mouse_pos) {
+            self.selected = self.filtered.get(index + self.start_view_index).copied();
+            self.update_list(renderer);
+        }
+    }
+
+    pub fn select_filter(&mut self, mouse_pos: Vec2) {
+        self.editing_index = None;
+        for (index, textbox) in self.filter_boxes.iter_mut().enumerate() {
+            if mouse_pos.x >= textbox.position.x
+                && mouse_pos.x <= textbox.position.x + textbox.size.x
+                && mouse_pos.y >= textbox.position.y
+                && mouse_pos.y <= textbox.position.y + textbox.size.y
+            {
+                textbox.set_border_color(Color::rgba(180, 180, 180, 255));
+                self.editing_index = Some(index);
+            } else {
+                textbox.set_border_color(Color::rgba(80, 80, 80, 255));
+            }
+        }
+    }
+
+    pub fn update_filter_text(&mut self, renderer: &mut GpuRenderer) {
+        if let Some(index) = self.editing_index {
+            self.filter_texts[index].set_text(renderer, &self.filter_data[index], Attrs::new());
+        }
+    }
+
+    pub fn hover(&mut self, renderer: &mut GpuRenderer, mouse_pos: Vec2) {
+        self.buttons.iter_mut().for_each(|button| {
+            button.set_hover(mouse_pos.x >= button.image.pos.x
+                && mouse_pos.x <= button.image.pos.x + button.image.hw.x
+                && mouse_pos.y >= button.image.pos.y
+                && mouse_pos.y <= button.image.pos.y + button.image.hw.y);
+        });
+        self.scrollbar.set_hover(mouse_pos);
+
+        let hover_row = self.find_row(mouse_pos);
+        if self.hover_row != hover_row {
+            self.hover_row = hover_row;
+            self.update_list(renderer);
+        }
+    }
+
+    pub fn click_buttons(&mut self, mouse_pos: Vec2) -> DialogButtonType {
+        let mut button_type = DialogButtonType::ButtonNone;
+        if let Some(button) = self.buttons.iter_mut().find(|button| {
+            mouse_pos.x >= button.image.pos.x
+                && mouse_pos.x <= button.image.pos.x + button.image.hw.x
+                && mouse_pos.y >= button.image.pos.y
+                && mouse_pos.y <= button.image.pos.y + button.image.hw.y
+        }) {
+            button.set_click(true);
+            button_type = button.button_type.clone();
+        }
+        if button_type != DialogButtonType::ButtonNone {
+            self.did_click = true;
+        }
+        button_type
+    }
+
+    pub fn release_click(&mut self) {
+        if !self.did_click {
+            return;
+        }
+        self.did_click = false;
+        self.buttons.iter_mut().for_each(|button| {
+            button.set_click(false);
+        });
+    }
+}
@@ -0,0 +1,246 @@
+use graphics::*;
+use cosmic_text::Attrs;
+use winit::dpi::PhysicalSize;
+use crate::map_data::*;
+use crate::interface::create_label;
+
+pub const PROP_NAME: usize = 0;
+pub const PROP_MUSIC: usize = 1;
+pub const PROP_WEATHER: usize = 2;
+pub const PROP_FOG: usize = 3;
+pub const PROP_FOG_OPACITY: usize = 4;
+pub const PROP_AMBIENT: usize = 5; // R, G, B, A
+pub const PROP_ZONE: usize = 9;
+pub const PROP_RESPAWN: usize = 10; // Map X, Map Y, Map Group, Tile X, Tile Y
+pub const PROP_CUSTOM: usize = 15; // Key and Value of each custom property
+
+// Only the first custom properties can be edited on the tab, the rest are kept as it is
+pub const MAX_CUSTOM_PROPERTY: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Text,
+    Numeric { can_be_negative: bool },
+    // Clicking on the box will switch to the next choice
+    Choice,
+}
+
+pub struct PropertyBox {
+    pub rect: Rect,
+    pub text: Text,
+    pub data: String,
+    pub field_type: FieldType,
+    pub limit: usize,
+}
+
+pub struct PropertySetting {
+    pub labels: Vec<Text>,
+    pub boxes: Vec<PropertyBox>,
+    pub editing_index: Option<usize>,
+}
+
+impl PropertySetting {
+    pub fn new(renderer: &mut GpuRenderer, size: &PhysicalSize<f32>, scale: f64) -> Self {
+        // Each row has a label and the box size of each value within the row
+        let numeric = FieldType::Numeric { can_be_negative: false };
+        let rows: Vec<(&str, Vec<(f32, FieldType, usize)>)> = vec![
+            ("Name", vec![(124.0, FieldType::Text, 32)]),
+            ("Music", vec![(124.0, FieldType::Text, 32)]),
+            ("Weather", vec![(124.0, FieldType::Choice, 0)]),
+            ("Fog", vec![(124.0, FieldType::Text, 32)]),
+            ("Fog Alpha", vec![(124.0, numeric, 3)]),
+            ("Ambient", vec![(28.0, numeric, 3); 4]),
+            ("Zone", vec![(124.0, FieldType::Choice, 0)]),
+            ("Respawn", vec![(38.0, FieldType::Numeric { can_be_negative: true }, 5), (38.0, FieldType::Numeric { can_be_negative: true }, 5), (38.0, numeric, 5)]),
+            ("Spawn Tile", vec![(60.0, numeric, 2); 2]),
+        ];
+
+        let mut labels = Vec::with_capacity(rows.len());
+        let mut boxes = Vec::with_capacity(PROP_CUSTOM + (MAX_CUSTOM_PROPERTY * 2));
+        let mut pos_y = 302.0;
+        for (name, fields) in rows.iter() {
+            let mut label = create_label(renderer, size, scale,
+                Vec3::new(14.0, pos_y, 1.9),
+                Vec2::new(66.0, 20.0),
+                Color::rgba(120, 120, 120, 255));
+            label.set_text(renderer, name, Attrs::new());
+            labels.push(label);
+
+            let mut pos_x = 82.0;
+            for (width, field_type, limit) in fields.iter() {
+                boxes.push(create_box(renderer, size, scale, Vec2::new(pos_x, pos_y), *width, *field_type, *limit));
+                pos_x += width + 4.0;
+            }
+            pos_y -= 22.0;
+        }
+
+        // Custom properties does not have a label, the key will be entered on the left box
+        for _ in 0..MAX_CUSTOM_PROPERTY {
+            boxes.push(create_box(renderer, size, scale, Vec2::new(14.0, pos_y), 94.0, FieldType::Text, 16));
+            boxes.push(create_box(renderer, size, scale, Vec2::new(112.0, pos_y), 94.0, FieldType::Text, 32));
+            pos_y -= 22.0;
+        }
+
+        Self {
+            labels,
+            boxes,
+            editing_index: None,
+        }
+    }
+
+    // We need to update the render data whenever the tab is being shown again
+    pub fn set_changed(&mut self) {
+        self.labels.iter_mut().for_each(|label| label.changed = true);
+        self.boxes.iter_mut().for_each(|textbox| {
+            textbox.rect.changed = true;
+            textbox.text.changed = true;
+        });
+    }
+
+    // Show the properties of the map that has been loaded
+    pub fn load_properties(&mut self, renderer: &mut GpuRenderer, properties: &MapProperties) {
+        let mut values = vec![String::new(); self.boxes.len()];
+        values[PROP_NAME] = properties.name.clone();
+        values[PROP_MUSIC] = properties.music.clone();
+        values[PROP_WEATHER] = properties.weather.as_str().to_string();
+        values[PROP_FOG] = properties.fog.clone();
+        values[PROP_FOG_OPACITY] = properties.fog_opacity.to_string();
+        for (index, color) in properties.ambient_color.iter().enumerate() {
+            values[PROP_AMBIENT + index] = color.to_string();
+        }
+        values[PROP_ZONE] = properties.zone.as_str().to_string();
+        if let Some(respawn) = properties.respawn {
+            values[PROP_RESPAWN] = respawn.map_x.to_string();
+            values[PROP_RESPAWN + 1] = respawn.map_y.to_string();
+            values[PROP_RESPAWN + 2] = respawn.map_group.to_string();
+            values[PROP_RESPAWN + 3] = respawn.tile_x.to_string();
+            values[PROP_RESPAWN + 4] = respawn.tile_y.to_string();
+        }
+        for (index, custom) in properties.custom.iter().take(MAX_CUSTOM_PROPERTY).enumerate() {
+            values[PROP_CUSTOM + (index * 2)] = custom.key.clone();
+            values[PROP_CUSTOM + (index * 2) + 1] = custom.value.clone();
+        }
+
+        for (textbox, value) in self.boxes.iter_mut().zip(values) {
+            textbox.data = value;
+            textbox.text.set_text(renderer, &textbox.data, Attrs::new());
+        }
+        self.select_box(Vec2::new(-1.0, -1.0));
+    }
+
+    // Returns the index of the box that is within the mouse position
+    pub fn select_box(&mut self, mouse_pos: Vec2) -> Option<usize> {
+        self.editing_index = None;
+        let mut found = None;
+        for (index, textbox) in self.boxes.iter_mut().enumerate() {
+            if mouse_pos.x >= textbox.rect.position.x
+                && mouse_pos.x <= textbox.rect.position.x + textbox.rect.size.x
+                && mouse_pos.y >= textbox.rect.position.y
+                && mouse_pos.y <= textbox.rect.position.y + textbox.rect.size.y
+            {
+                found = Some(index);
+                if textbox.field_type != FieldType::Choice {
+                    textbox.rect.set_border_color(Color::rgba(180, 180, 180, 255));
+                    self.editing_index = Some(index);
+                    continue;
+                }
+            }
+            textbox.rect.set_border_color(Color::rgba(80, 80, 80, 255));
+        }
+        found
+    }
+
+    // Switch the choice box to its next value, returns false if the box is not a choice
+    pub fn next_choice(&mut self, renderer: &mut GpuRenderer, index: usize) -> bool {
+        let choices: Vec<&str> = match index {
+            PROP_WEATHER => Weather::LIST.iter().map(|weather| weather.as_str()).collect(),
+            PROP_ZONE => ZoneType::LIST.iter().map(|zone| zone.as_str()).collect(),
+            _ => return false,
+        };
+        let textbox = &mut self.boxes[index];
+        let current = choices.iter().position(|choice| *choice == textbox.data).unwrap_or_default();
+        textbox.data = choices[(current + 1) % choices.len()].to_string();
+        textbox.text.set_text(renderer, &textbox.data, Attrs::new());
+        true
+    }
+
+    pub fn update_box_text(&mut self, renderer: &mut GpuRenderer) {
+        if let Some(index) = self.editing_index {
+            let textbox = &mut self.boxes[index];
+            textbox.text.set_text(renderer, &textbox.data, Attrs::new());
+        }
+    }
+
+    // Write the values on the tab to the map properties
+    pub fn apply_properties(&self, properties: &mut MapProperties) {
+        let text = |index: usize| self.boxes[index].data.trim().to_string();
+        let value = |index: usize| self.boxes[index].data.parse::<i64>().ok();
+
+        properties.name = text(PROP_NAME);
+        properties.music = text(PROP_MUSIC);
+        properties.weather = Weather::LIST.into_iter()
+            .find(|weather| weather.as_str() == self.boxes[PROP_WEATHER].data)
+            .unwrap_or_default();
+        properties.fog = text(PROP_FOG);
+        properties.fog_opacity = value(PROP_FOG_OPACITY).unwrap_or_default().clamp(0, 255) as u8;
+        for index in 0..4 {
+            properties.ambient_color[index] = value(PROP_AMBIENT + index).unwrap_or(255).clamp(0, 255) as u8;
+        }
+        properties.zone = ZoneType::LIST.into_iter()
+            .find(|zone| zone.as_str() == self.boxes[PROP_ZONE].data)
+            .unwrap_or_default();
+
+        // The map has no respawn point when every value is empty
+        properties.respawn = if (PROP_RESPAWN..PROP_CUSTOM).all(|index| self.boxes[index].data.is_empty()) {
+            None
+        } else {
+            Some(RespawnPoint {
+                map_x: value(PROP_RESPAWN).unwrap_or_default() as i32,
+                map_y: value(PROP_RESPAWN + 1).unwrap_or_default() as i32,
+                map_group: value(PROP_RESPAWN + 2).unwrap_or_default().max(0) as u64,
+                tile_x: value(PROP_RESPAWN + 3).unwrap_or_default().clamp(0, 31) as u32,
+                tile_y: value(PROP_RESPAWN + 4).unwrap_or_default().clamp(0, 31) as u32,
+            })
+        };
+
+        // Custom properties without a key are removed
+        let mut custom: Vec<CustomProperty> = (0..MAX_CUSTOM_PROPERTY).filter_map(|index| {
+            let key = text(PROP_CUSTOM + (index * 2));
+            if key.is_empty() {
+                return None;
+            }
+            Some(CustomProperty { key, value: text(PROP_CUSTOM + (index * 2) + 1) })
+        }).collect();
+        custom.extend(properties.custom.iter().skip(MAX_CUSTOM_PROPERTY).cloned());
+        properties.custom = custom;
+    }
+}
+
+fn create_box(renderer: &mut GpuRenderer,
+                size: &PhysicalSize<f32>,
+                scale: f64,
+                pos: Vec2,
+                width: f32,
+                field_type: FieldType,
+                limit: usize,
+) -> PropertyBox {
+    let mut rect = Rect::new(renderer, 0);
+    rect.set_size(Vec2::new(width, 20.0))
+        .set_position(Vec3::new(pos.x, pos.y, 9.0))
+        .set_border_color(Color::rgba(80, 80, 80, 255))
+        .set_border_width(1.0)
+        .set_color(if field_type == FieldType::Choice { Color::rgba(60, 60, 60, 255) } else { Color::rgba(80, 80, 80, 255) });
+
+    let text = create_label(renderer, size, scale,
+        Vec3::new(pos.x + 3.0, pos.y, 1.9),
+        Vec2::new(width - 6.0, 20.0),
+        Color::rgba(200, 200, 200, 255));
+
+    PropertyBox {
+        rect,
+        text,
+        data: String::new(),
+        field_type,
+        limit,
+    }
+}
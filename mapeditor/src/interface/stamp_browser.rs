@@ -0,0 +1,325 @@
+use graphics::*;
+use cosmic_text::Attrs;
+use winit::dpi::PhysicalSize;
+use crate::resource::*;
+use crate::collection::ZOOM_LEVEL;
+use crate::map_data::*;
+use crate::interface::{
+    create_label,
+    dialog::*,
+    scrollbar::*,
+};
+
+const MAX_VISIBLE_ROW: usize = 11;
+const ROW_HEIGHT: f32 = 30.0;
+
+// List of the stamps of the project, the map selection or the tileset selection can be saved as a new stamp
+pub struct StampBrowser {
+    pub bg: Image,
+    pub window: Rect,
+    pub message: Text,
+    pub count_label: Text,
+    pub name_label: Text,
+    pub name_box: Rect,
+    pub name_text: Text,
+    pub name_data: String,
+    pub is_editing: bool,
+    pub list_box: Vec<Rect>,
+    pub rows: Vec<Rect>,
+    pub row_texts: Vec<Text>,
+    pub buttons: Vec<DialogButton>,
+    pub scrollbar: Scrollbar,
+    library: StampLibrary,
+    start_view_index: usize,
+    selected: Option<usize>,
+    hover_row: Option<usize>,
+    did_click: bool,
+    scrollbar_pos: Vec3,
+}
+
+impl StampBrowser {
+    pub fn new(resource: &TextureAllocation,
+                renderer: &mut GpuRenderer,
+                size: &PhysicalSize<f32>,
+                scale: f64,
+                library: StampLibrary) -> Self {
+        // This image is for the transparent shadow that will render behind the stamp browser
+        let mut bg = Image::new(Some(resource.white.allocation), renderer, 1);
+        bg.pos = Vec3::new(0.0, 0.0, 0.9);
+        bg.hw = Vec2::new(size.width, size.height);
+        bg.uv = Vec4::new(2.0, 2.0, 17.0, 17.0);
+        bg.color = Color::rgba(0, 0, 0, 200);
+
+        let window_size = Vec2::new(456.0, 500.0);
+        let window_pos = Vec2::new((size.width * 0.5) - ((window_size.x * 0.5) * ZOOM_LEVEL),
+                            (size.height * 0.5) - ((window_size.y * 0.5) * ZOOM_LEVEL)).floor();
+        let mut window = Rect::new(renderer, 0);
+        window.set_size(window_size)
+            .set_position(Vec3::new(window_pos.x, window_pos.y, 0.8))
+            .set_radius(3.0)
+            .set_border_color(Color::rgba(10, 10, 10, 255))
+            .set_border_width(2.0)
+            .set_color(Color::rgba(50, 50, 50, 255));
+
+        let message = create_label(renderer, size, scale,
+            Vec3::new(window_pos.x, window_pos.y + 466.0, 0.6),
+            Vec2::new(window_size.x, 20.0),
+            Color::rgba(120, 120, 120, 255));
+
+        // List
+        let content_x = window_pos.x + 18.0;
+        let list_pos = Vec2::new(content_x, window_pos.y + 96.0);
+        let list_size = Vec2::new(420.0, 340.0);
+        let mut label_box = Rect::new(renderer, 0);
+        label_box.set_size(list_size)
+            .set_position(Vec3::new(list_pos.x, list_pos.y, 0.78))
+            .set_color(Color::rgba(60, 60, 60, 255));
+        let mut scrollbar_box = Rect::new(renderer, 0);
+        scrollbar_box.set_size(Vec2::new(8.0, list_size.y - 4.0))
+            .set_position(Vec3::new(list_pos.x + 410.0, list_pos.y + 2.0, 0.7))
+            .set_color(Color::rgba(40, 40, 40, 255));
+        let list_box = vec![label_box, scrollbar_box];
+
+        let mut rows = Vec::with_capacity(MAX_VISIBLE_ROW);
+        let mut row_texts = Vec::with_capacity(MAX_VISIBLE_ROW);
+        for index in 0..MAX_VISIBLE_ROW {
+            let row_y = list_pos.y + list_size.y - 2.0 - (ROW_HEIGHT * (index + 1) as f32);
+            let mut row = Rect::new(renderer, 0);
+            row.set_size(Vec2::new(404.0, ROW_HEIGHT - 2.0))
+                .set_position(Vec3::new(list_pos.x + 2.0, row_y, 0.75))
+                .set_color(Color::rgba(60, 60, 60, 255));
+            rows.push(row);
+
+            let mut text = create_label(renderer, size, scale,
+                Vec3::new(list_pos.x + 8.0, row_y + 4.0, 0.6),
+                Vec2::new(394.0, 20.0),
+                Color::rgba(180, 180, 180, 255));
+            text.set_text(renderer, "", Attrs::new());
+            row_texts.push(text);
+        }
+
+        let mut count_label = create_label(renderer, size, scale,
+            Vec3::new(list_pos.x, list_pos.y + list_size.y + 6.0, 0.6),
+            Vec2::new(list_size.x, 20.0),
+            Color::rgba(120, 120, 120, 255));
+        count_label.set_text(renderer, "", Attrs::new());
+
+        // Name of the stamp that will be saved, Name[45][375]
+        let name_y = window_pos.y + 60.0;
+        let mut name_label = create_label(renderer, size, scale,
+            Vec3::new(content_x, name_y, 0.6),
+            Vec2::new(45.0, 20.0),
+            Color::rgba(120, 120, 120, 255));
+        name_label.set_text(renderer, "Name", Attrs::new());
+        let mut name_box = Rect::new(renderer, 0);
+        name_box.set_size(Vec2::new(375.0, 24.0))
+            .set_position(Vec3::new(content_x + 45.0, name_y, 0.7))
+            .set_border_color(Color::rgba(80, 80, 80, 255))
+            .set_border_width(1.0)
+            .set_color(Color::rgba(80, 80, 80, 255));
+        let mut name_text = create_label(renderer, size, scale,
+            Vec3::new(content_x + 47.0, name_y, 0.6),
+            Vec2::new(371.0, 20.0),
+            Color::rgba(200, 200, 200, 255));
+        name_text.set_text(renderer, "", Attrs::new());
+
+        let button_pos = Vec2::new(content_x - 4.0, window_pos.y + 18.0);
+        let buttons = vec![
+            DialogButton::new(resource, renderer, size, scale, "Use", button_pos, Vec2::new(103.0, 20.0), DialogButtonType::ButtonConfirm),
+            DialogButton::new(resource, renderer, size, scale, "Save", button_pos + Vec2::new(107.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonDecline),
+            DialogButton::new(resource, renderer, size, scale, "Delete", button_pos + Vec2::new(214.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonDelete),
+            DialogButton::new(resource, renderer, size, scale, "Cancel", button_pos + Vec2::new(321.0, 0.0), Vec2::new(103.0, 20.0), DialogButtonType::ButtonCancel),
+        ];
+
+        let scrollbar_pos = Vec3::new(list_pos.x + 409.0, list_pos.y + list_size.y - 5.0, 0.5);
+        let scrollbar = Scrollbar::new(resource, renderer, scrollbar_pos, 0, 330, 20);
+
+        let mut stamp_browser = Self {
+            bg,
+            window,
+            message,
+            count_label,
+            name_label,
+            name_box,
+            name_text,
+            name_data: String::new(),
+            is_editing: false,
+            list_box,
+            rows,
+            row_texts,
+            buttons,
+            scrollbar,
+            library,
+            start_view_index: 0,
+            selected: None,
+            hover_row: None,
+            did_click: false,
+            scrollbar_pos,
+        };
+        stamp_browser.set_message(renderer, "Save the map or tileset selection, or pick a stamp to draw");
+        stamp_browser.reload_list(resource, renderer);
+        stamp_browser
+    }
+
+    pub fn set_message(&mut self, renderer: &mut GpuRenderer, msg: &str) {
+        self.message.set_text(renderer, msg, Attrs::new());
+        let message_size = self.message.measure();
+        self.message.pos.x = self.window.position.x + ((self.window.size.x * 0.5) - (message_size.x * 0.5)).floor();
+        self.message.changed = true;
+    }
+
+    // Rebuild the list after the library has changed, the scrollbar is recreated as the list size has changed
+    fn reload_list(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer) {
+        let scrollbar_amount = self.library.stamps.len().max(MAX_VISIBLE_ROW) - MAX_VISIBLE_ROW;
+        self.scrollbar = Scrollbar::new(resource, renderer, self.scrollbar_pos, scrollbar_amount, 330, 20);
+        self.start_view_index = 0;
+
+        self.count_label.set_text(renderer, &format!("{} stamp/s", self.library.stamps.len()), Attrs::new());
+        self.update_list(renderer);
+    }
+
+    pub fn update_list(&mut self, renderer: &mut GpuRenderer) {
+        for index in 0..MAX_VISIBLE_ROW {
+            let stamp_index = index + self.start_view_index;
+            let msg = match self.library.stamps.get(stamp_index) {
+                Some(stamp) => format!("{}  [ {} x {} ]  {} layer/s", stamp.name, stamp.width, stamp.height, stamp.layers.len()),
+                None => String::new(),
+            };
+            self.row_texts[index].set_text(renderer, &msg, Attrs::new());
+
+            let color = if Some(stamp_index) == self.selected {
+                Color::rgba(95, 95, 95, 255)
+            } else if Some(index) == self.hover_row {
+                Color::rgba(75, 75, 75, 255)
+            } else {
+                Color::rgba(60, 60, 60, 255)
+            };
+            self.rows[index].set_color(color);
+        }
+    }
+
+    pub fn update_scroll(&mut self, scroll_index: usize) -> bool {
+        if self.start_view_index != scroll_index {
+            self.start_view_index = scroll_index;
+            return true;
+        }
+        false
+    }
+
+    // Amount of rows that has a stamp on it, only these rows are rendered
+    pub fn visible_rows(&self) -> usize {
+        self.library.stamps.len().saturating_sub(self.start_view_index).min(MAX_VISIBLE_ROW)
+    }
+
+    fn find_row(&self, mouse_pos: Vec2) -> Option<usize> {
+        self.rows.iter().take(self.visible_rows()).position(|row| {
+            mouse_pos.x >= row.position.x
+                && mouse_pos.x <= row.position.x + row.size.x
+                && mouse_pos.y >= row.position.y
+                && mouse_pos.y <= row.position.y + row.size.y
+        })
+    }
+
+    pub fn get_selected(&self) -> Option<&LibraryStamp> {
+        self.library.stamps.get(self.selected?)
+    }
+
+    // Picking a stamp also fills its name, so it can be replaced by saving again
+    pub fn select_row(&mut self, renderer: &mut GpuRenderer, mouse_pos: Vec2) {
+        if let Some(index) = self.find_row(mouse_pos) {
+            self.selected = Some(index + self.start_view_index);
+            if let Some(stamp) = self.get_selected() {
+                self.name_data = stamp.name.clone();
+            }
+            self.update_name_text(renderer);
+            self.update_list(renderer);
+        }
+    }
+
+    pub fn select_textbox(&mut self, mouse_pos: Vec2) {
+        self.is_editing = mouse_pos.x >= self.name_box.position.x
+            && mouse_pos.x <= self.name_box.position.x + self.name_box.size.x
+            && mouse_pos.y >= self.name_box.position.y
+            && mouse_pos.y <= self.name_box.position.y + self.name_box.size.y;
+        self.name_box.set_border_color(if self.is_editing {
+            Color::rgba(180, 180, 180, 255)
+        } else {
+            Color::rgba(80, 80, 80, 255)
+        });
+    }
+
+    pub fn update_name_text(&mut self, renderer: &mut GpuRenderer) {
+        self.name_text.set_text(renderer, &self.name_data, Attrs::new());
+    }
+
+    // Save the stamp on the library file, the saved stamp is selected on the list
+    pub fn add_stamp(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer, stamp: LibraryStamp) -> Result<(), AscendingError> {
+        let name = stamp.name.clone();
+        let mut library = self.library.clone();
+        library.add(stamp);
+        library.save()?;
+
+        self.library = library;
+        self.selected = self.library.stamps.iter().position(|stamp| stamp.name == name);
+        self.reload_list(resource, renderer);
+        Ok(())
+    }
+
+    pub fn remove_selected(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer) -> Result<Option<String>, AscendingError> {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let mut library = self.library.clone();
+        let removed = library.remove(index);
+        library.save()?;
+
+        self.library = library;
+        self.selected = None;
+        self.reload_list(resource, renderer);
+        Ok(removed.map(|stamp| stamp.name))
+    }
+
+    pub fn hover(&mut self, renderer: &mut GpuRenderer, mouse_pos: Vec2) {
+        self.buttons.iter_mut().for_each(|button| {
+            button.set_hover(mouse_pos.x >= button.image.pos.x
+                && mouse_pos.x <= button.image.pos.x + button.image.hw.x
+                && mouse_pos.y >= button.image.pos.y
+                && mouse_pos.y <= button.image.pos.y + button.image.hw.y);
+        });
+        self.scrollbar.set_hover(mouse_pos);
+
+        let hover_row = self.find_row(mouse_pos);
+        if self.hover_row != hover_row {
+            self.hover_row = hover_row;
+            self.update_list(renderer);
+        }
+    }
+
+    pub fn click_buttons(&mut self, mouse_pos: Vec2) -> DialogButtonType {
+        let mut button_type = DialogButtonType::ButtonNone;
+        if let Some(button) = self.buttons.iter_mut().find(|button| {
+            mouse_pos.x >= button.image.pos.x
+                && mouse_pos.x <= button.image.pos.x + button.image.hw.x
+                && mouse_pos.y >= button.image.pos.y
+                && mouse_pos.y <= button.image.pos.y + button.image.hw.y
+        }) {
+            button.set_click(true);
+            button_type = button.button_type.clone();
+        }
+        if button_type != DialogButtonType::ButtonNone {
+            self.did_click = true;
+        }
+        button_type
+    }
+
+    pub fn release_click(&mut self) {
+        if !self.did_click {
+            return;
+        }
+        self.did_click = false;
+        self.buttons.iter_mut().for_each(|button| {
+            button.set_click(false);
+        });
+    }
+}
@@ -136,6 +136,23 @@ impl TilesetList {
         false
     }
 
+    // This is used when the tileset has been switched without clicking on the list
+    pub fn set_selected_tileset(&mut self, tileset_index: usize) {
+        if self.selected_tileset == tileset_index {
+            return;
+        }
+        self.selected_tileset = tileset_index;
+        self.view_index = None;
+        for (index, button) in self.selection_buttons.iter_mut().enumerate() {
+            if index + self.start_view_index == tileset_index {
+                button.set_select(true);
+                self.view_index = Some(index);
+            } else {
+                button.set_select(false);
+            }
+        }
+    }
+
     // We use this function to update the list when the start view index has been adjusted
     pub fn update_list(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer) {
         if !self.visible {
@@ -0,0 +1,252 @@
+use graphics::*;
+use cosmic_text::Attrs;
+use winit::dpi::PhysicalSize;
+use log::warn;
+use crate::resource::*;
+use crate::collection::ZOOM_LEVEL;
+use crate::map_data::*;
+use crate::interface::create_label;
+
+const THUMBNAIL_SIZE: f32 = 48.0;
+const THUMBNAIL_SPACING: f32 = 6.0;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+// The mouse must move this far before a click on a map is handled as a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+
+pub struct WorldThumbnail {
+    pub x: i32,
+    pub y: i32,
+    pub image: Image,
+    pub marker: Rect,
+    pub visible: bool,
+    is_unsaved: bool,
+}
+
+// Overview of every map of a group, each map is shown as a thumbnail with one pixel per tile
+pub struct WorldView {
+    pub group: u64,
+    pub bg: Image,
+    pub window: Rect,
+    pub title: Text,
+    pub hover_label: Text,
+    pub thumbnails: Vec<WorldThumbnail>,
+    view_pos: Vec2,
+    view_size: Vec2,
+    // Map location that is on the center of the view
+    center: Vec2,
+    zoom: f32,
+    hold_pos: Option<Vec2>,
+    did_drag: bool,
+}
+
+impl WorldView {
+    pub fn new(resource: &TextureAllocation,
+                renderer: &mut GpuRenderer,
+                atlas: &mut AtlasSet,
+                size: &PhysicalSize<f32>,
+                scale: f64,
+                editor_data: &EditorData) -> Self {
+        let group = editor_data.group;
+
+        // This image is for the transparent shadow that will render behind the world view
+        let mut bg = Image::new(Some(resource.white.allocation), renderer, 1);
+        bg.pos = Vec3::new(0.0, 0.0, 0.9);
+        bg.hw = Vec2::new(size.width, size.height);
+        bg.uv = Vec4::new(2.0, 2.0, 17.0, 17.0);
+        bg.color = Color::rgba(0, 0, 0, 200);
+
+        let window_size = Vec2::new(889.0, 742.0);
+        let window_pos = Vec2::new((size.width * 0.5) - ((window_size.x * 0.5) * ZOOM_LEVEL),
+                            (size.height * 0.5) - ((window_size.y * 0.5) * ZOOM_LEVEL)).floor();
+        let mut window = Rect::new(renderer, 0);
+        window.set_size(window_size)
+            .set_position(Vec3::new(window_pos.x, window_pos.y, 0.8))
+            .set_radius(3.0)
+            .set_border_color(Color::rgba(10, 10, 10, 255))
+            .set_border_width(2.0)
+            .set_color(Color::rgba(50, 50, 50, 255));
+
+        let view_pos = window_pos + Vec2::new(10.0, 40.0);
+        let view_size = window_size - Vec2::new(20.0, 80.0);
+
+        let mut title = create_label(renderer, size, scale,
+            Vec3::new(window_pos.x + 10.0, window_pos.y + window_size.y - 30.0, 0.6),
+            Vec2::new(window_size.x - 20.0, 20.0),
+            Color::rgba(180, 180, 180, 255));
+        title.set_text(renderer, &format!("World [ Group: {} ]  Drag to move, scroll to zoom, click to open", group), Attrs::new());
+        let mut hover_label = create_label(renderer, size, scale,
+            Vec3::new(window_pos.x + 10.0, window_pos.y + 12.0, 0.6),
+            Vec2::new(window_size.x - 20.0, 20.0),
+            Color::rgba(180, 180, 180, 255));
+        hover_label.set_text(renderer, "", Attrs::new());
+
+        let colors = TileColors::load().unwrap_or_else(|e| {
+            warn!("Failed to read the tilesheets, Err {:?}", e);
+            TileColors::default()
+        });
+        let locations: Vec<(i32, i32)> = editor_data.list_maps().into_iter()
+            .filter(|(_, _, map_group)| *map_group == group)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let mut thumbnails = Vec::with_capacity(locations.len());
+        for (x, y) in locations {
+            let key = format!("{}_{}_{}", x, y, group);
+            let data = match editor_data.peek_map(x, y, group) {
+                Ok(Some(data)) => data,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read map {}, Err {:?}", key, e);
+                    continue;
+                }
+            };
+            let texture = upload_map_thumbnail(renderer, atlas, &key, &data, &colors);
+
+            let mut image = Image::new(texture, renderer, 1);
+            image.uv = Vec4::new(0.0, 0.0, 32.0, 32.0);
+            image.color = Color::rgba(255, 255, 255, 255);
+
+            let is_unsaved = editor_data.did_map_change.get(&key).copied().unwrap_or_default();
+            let mut marker = Rect::new(renderer, 0);
+            marker.set_color(Color::rgba(0, 0, 0, 0))
+                .set_border_width(2.0)
+                .set_border_color(if is_unsaved {
+                    Color::rgba(230, 150, 30, 255)
+                } else if key == editor_data.current_index {
+                    Color::rgba(220, 220, 220, 255)
+                } else {
+                    Color::rgba(80, 80, 80, 255)
+                });
+
+            thumbnails.push(WorldThumbnail { x, y, image, marker, visible: false, is_unsaved });
+        }
+
+        let mut world_view = Self {
+            group,
+            bg,
+            window,
+            title,
+            hover_label,
+            thumbnails,
+            view_pos,
+            view_size,
+            center: Vec2::new(editor_data.x as f32, editor_data.y as f32),
+            zoom: 1.0,
+            hold_pos: None,
+            did_drag: false,
+        };
+        world_view.update_layout();
+        world_view
+    }
+
+    fn cell_size(&self) -> f32 {
+        (THUMBNAIL_SIZE + THUMBNAIL_SPACING) * self.zoom
+    }
+
+    fn view_center(&self) -> Vec2 {
+        self.view_pos + (self.view_size * 0.5)
+    }
+
+    // Place the thumbnails based on the center and zoom, only the thumbnails that are completely within the view are shown
+    fn update_layout(&mut self) {
+        let (cell_size, view_center) = (self.cell_size(), self.view_center());
+        let thumbnail_size = THUMBNAIL_SIZE * self.zoom;
+        let (view_pos, view_end) = (self.view_pos, self.view_pos + self.view_size);
+
+        for thumbnail in self.thumbnails.iter_mut() {
+            let pos = view_center + (Vec2::new(thumbnail.x as f32, thumbnail.y as f32) - self.center) * cell_size
+                - (thumbnail_size * 0.5);
+            thumbnail.visible = pos.x >= view_pos.x && pos.y >= view_pos.y
+                && pos.x + thumbnail_size <= view_end.x && pos.y + thumbnail_size <= view_end.y;
+
+            thumbnail.image.pos = Vec3::new(pos.x, pos.y, 0.7);
+            thumbnail.image.hw = Vec2::new(thumbnail_size, thumbnail_size);
+            thumbnail.image.changed = true;
+            thumbnail.marker.set_position(Vec3::new(pos.x - 2.0, pos.y - 2.0, 0.65))
+                .set_size(Vec2::new(thumbnail_size + 4.0, thumbnail_size + 4.0));
+        }
+    }
+
+    pub fn in_view(&self, mouse_pos: Vec2) -> bool {
+        mouse_pos.x >= self.view_pos.x && mouse_pos.x <= self.view_pos.x + self.view_size.x
+            && mouse_pos.y >= self.view_pos.y && mouse_pos.y <= self.view_pos.y + self.view_size.y
+    }
+
+    fn find_thumbnail(&self, mouse_pos: Vec2) -> Option<&WorldThumbnail> {
+        self.thumbnails.iter().find(|thumbnail| {
+            thumbnail.visible
+                && mouse_pos.x >= thumbnail.image.pos.x
+                && mouse_pos.x <= thumbnail.image.pos.x + thumbnail.image.hw.x
+                && mouse_pos.y >= thumbnail.image.pos.y
+                && mouse_pos.y <= thumbnail.image.pos.y + thumbnail.image.hw.y
+        })
+    }
+
+    pub fn hold(&mut self, mouse_pos: Vec2) {
+        if self.in_view(mouse_pos) {
+            self.hold_pos = Some(mouse_pos);
+            self.did_drag = false;
+        }
+    }
+
+    pub fn drag(&mut self, mouse_pos: Vec2) {
+        let hold_pos = match self.hold_pos {
+            Some(hold_pos) => hold_pos,
+            None => return,
+        };
+        let distance = mouse_pos - hold_pos;
+        if !self.did_drag && distance.length() < DRAG_THRESHOLD {
+            return;
+        }
+        self.did_drag = true;
+        self.center -= distance / self.cell_size();
+        self.hold_pos = Some(mouse_pos);
+        self.update_layout();
+    }
+
+    // Returns the location of the map that has been clicked, nothing is returned when the view was dragged
+    pub fn release(&mut self) -> Option<(i32, i32)> {
+        let hold_pos = self.hold_pos.take()?;
+        if self.did_drag {
+            return None;
+        }
+        self.find_thumbnail(hold_pos).map(|thumbnail| (thumbnail.x, thumbnail.y))
+    }
+
+    // The map location under the mouse stays on the same position while zooming
+    pub fn zoom_at(&mut self, mouse_pos: Vec2, amount: f32) {
+        if !self.in_view(mouse_pos) {
+            return;
+        }
+        let zoom = (self.zoom * 1.25f32.powf(amount)).clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom == self.zoom {
+            return;
+        }
+        let offset = mouse_pos - self.view_center();
+        let location = self.center + offset / self.cell_size();
+        self.zoom = zoom;
+        self.center = location - offset / self.cell_size();
+        self.update_layout();
+    }
+
+    pub fn hover(&mut self, renderer: &mut GpuRenderer, mouse_pos: Vec2) {
+        let msg = match self.find_thumbnail(mouse_pos) {
+            Some(thumbnail) if thumbnail.is_unsaved => format!("Map [ X: {} Y: {} Group: {} ] Unsaved", thumbnail.x, thumbnail.y, self.group),
+            Some(thumbnail) => format!("Map [ X: {} Y: {} Group: {} ]", thumbnail.x, thumbnail.y, self.group),
+            None => String::new(),
+        };
+        self.hover_label.set_text(renderer, &msg, Attrs::new());
+    }
+}
+
+// Upload the thumbnail of a map, the thumbnail of a previous upload is replaced as the map could have changed since then
+pub fn upload_map_thumbnail(renderer: &mut GpuRenderer,
+                    atlas: &mut AtlasSet,
+                    key: &str,
+                    data: &MapData,
+                    colors: &TileColors) -> Option<usize> {
+    let thumbnail = render_thumbnail(data, colors);
+    let texture_key = format!("map_thumbnail_{}", key);
+    atlas.remove_by_key(&texture_key);
+    atlas.upload(texture_key, thumbnail.as_raw(), 32, 32, 0, renderer)
+}
@@ -0,0 +1,2 @@
+// The map model and map files, these can be used without a window or a GpuRenderer
+pub mod map_data;
@@ -6,7 +6,7 @@ use camera::{
 };
 use cosmic_text::{Attrs, Metrics};
 use graphics::*;
-use input::{Bindings, FrameTime, InputHandler, Key};
+use input::{Bindings, Button, FrameTime, InputHandler, Key, MouseAxis, Named};
 use log::{error, info, warn, Level, LevelFilter, Metadata, Record};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -31,7 +31,6 @@ mod collection;
 mod tileset;
 mod game_input;
 mod map;
-mod map_data;
 
 use renderer::*;
 use interface::*;
@@ -40,7 +39,7 @@ use collection::*;
 use tileset::*;
 use game_input::*;
 use map::*;
-use map_data::*;
+use mapeditor::map_data::{self, *};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 enum Axis {
@@ -100,10 +99,35 @@ async fn main() -> Result<(), AscendingError> {
         let bt = Backtrace::new();
 
         error!("PANIC: {}, BACKTRACE: {:?}", panic_info, bt);
+
+        // Keep the unsaved maps so that they can be restored on the next start
+        if let Err(e) = write_recovery_snapshot() {
+            error!("Failed to write the recovery maps, Err {:?}", e);
+        }
     }));
 
     // Create the directory for our map data
     fs::create_dir_all("./data/maps/")?;
+    fs::create_dir_all(RECOVERY_PATH)?;
+
+    // Load the project settings, this deturmine the format that is used when saving the maps
+    load_project_config()?;
+
+    // Usage: mapeditor --convert-maps <json|binary>
+    // This convert every map to the given format, set it as the project format and exit
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(arg_index) = args.iter().position(|arg| arg == "--convert-maps") {
+        let format = match args.get(arg_index + 1).and_then(|name| MapFormat::from_name(name)) {
+            Some(format) => format,
+            None => return Err(AscendingError::Other(OtherError::new("Usage: mapeditor --convert-maps <json|binary>"))),
+        };
+        let count = convert_maps(format)?;
+        let mut config = project_config();
+        config.map_format = format;
+        save_project_config(config)?;
+        info!("Converted {} map/s to {:?}", count, format);
+        return Ok(());
+    }
 
     // Starts an event gathering type for the window.
     let event_loop = EventLoop::new()?;
@@ -190,16 +214,38 @@ async fn main() -> Result<(), AscendingError> {
     // Load textures image
     let resource = TextureAllocation::new(&mut atlases, &renderer)?;
 
+    // Convert maps that were saved with raw atlas positions into tile references
+    match migrate_legacy_maps(|texture_id| {
+        resource.get_tile_ref(texture_id, 0).map(|(name, tile_index)| (name.to_string(), tile_index))
+    }) {
+        Ok(count) if count > 0 => info!("Migrated {} map/s to tile references", count),
+        Ok(_) => {},
+        Err(e) => error!("Failed to migrate maps, Err {:?}", e),
+    }
+
     // Initiate map editor data
     let mut gui = Interface::new(&resource, &mut renderer, &size, scale);
     let mut tileset = Tileset::new(&resource, &mut renderer);
     let mut gameinput = GameInput::new();
     let mut mapview = MapView::new(&resource, &mut renderer);
-    let mut editor_data = EditorData::new()?;
+    let mut editor_data = EditorData::new();
+    // Maps that were left unsaved when the editor was closed unexpectedly
+    let recovery_list = get_recovery_list();
+    if !recovery_list.is_empty() {
+        gui.open_dialog(&resource, &mut renderer, &size, scale, DialogType::TypeMapRecovery, Some(recovery_list));
+    }
+    if let Err(e) = editor_data.init_map(0, 0, 0) {
+        error!("Failed to load the starting map, Err {:?}", e);
+        gui.open_error_dialog(&resource, &mut renderer, &size, scale, &e.to_string());
+        editor_data.init_default_map(0, 0, 0);
+    }
 
     // Load the initial map
-    editor_data.load_map_data(&mut mapview);
-    editor_data.load_link_maps(&mut mapview);
+    editor_data.load_map_data(&resource, &mut mapview.document);
+    editor_data.load_link_maps(&resource, &mut mapview.document);
+    load_map_properties(&mut renderer, &mut gui, &mut editor_data);
+    update_map_name(&mut renderer, &mut gui, &mut editor_data);
+    show_migrated_maps(&mut renderer, &resource, &mut gui, &size, scale, &mut editor_data);
 
     // setup our system which includes Camera and projection as well as our controls.
     // for the camera.
@@ -245,7 +291,15 @@ async fn main() -> Result<(), AscendingError> {
     };
 
     // Create the mouse/keyboard bindings for our stuff.
-    let bindings = Bindings::<Action, Axis>::new();
+    let mut bindings = Bindings::<Action, Axis>::new();
+    bindings.insert_action(Action::Undo, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('z'))]);
+    bindings.insert_action(Action::Redo, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('y'))]);
+    bindings.insert_action(Action::Redo, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('Z'))]);
+    bindings.insert_action(Action::WorldView, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('m'))]);
+    bindings.insert_action(Action::Copy, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('c'))]);
+    bindings.insert_action(Action::Cut, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('x'))]);
+    bindings.insert_action(Action::Paste, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('v'))]);
+    bindings.insert_action(Action::StampLibrary, vec![Button::Key(Key::Named(Named::Control)), Button::Key(Key::Character('b'))]);
 
     // set bindings and create our own input handler.
     let mut input_handler = InputHandler::new(bindings);
@@ -253,6 +307,7 @@ async fn main() -> Result<(), AscendingError> {
     let mut frame_time = FrameTime::new();
     let mut time = 0.0f32;
     let mut fps = 0u32;
+    let mut autosave_time = AUTOSAVE_INTERVAL;
 
     // This will prevent key press to trigger the action while holding down the key
     let mut did_key_press = [false; ACTION_SIZE];
@@ -268,6 +323,7 @@ async fn main() -> Result<(), AscendingError> {
             } if window_id == renderer.window().id() => {
                 match event {
                     WindowEvent::CloseRequested => {
+                        gui.world_view = None;
                         if editor_data.got_changes() {
                             // We found changes on our map, we need to confirm if we would like to proceed to exit the editor
                             gui.open_dialog(&resource, &mut renderer, &size, scale, DialogType::TypeMapSave, Some(editor_data.did_map_change.clone()));
@@ -277,8 +333,10 @@ async fn main() -> Result<(), AscendingError> {
                     }
                     WindowEvent::KeyboardInput { event, .. } => {
                         handle_key_input(&mut renderer,
+                                    &resource,
                                     event,
-                                    &mut gui);
+                                    &mut gui,
+                                    &mut editor_data);
                     }
                     _ => {}
                 }
@@ -370,24 +428,123 @@ async fn main() -> Result<(), AscendingError> {
                     &mut mapview,
                     &mut editor_data);
             }
-            mapview.record.stop_record();
+            mapview.document.stop_records();
+            // The stroke is done, the recovery copy now includes it
+            if editor_data.recovery_outdated {
+                editor_data.update_recovery_snapshot(&resource, &mapview.document);
+            }
             gui.reset_button_click();
             if let Some(dialog) = &mut gui.dialog {
                 dialog.release_click();
                 dialog.scrollbar.release_scrollbar();
             }
+            release_world_view(&mut renderer, &resource, &mut gui, &size, scale, &mut editor_data, &mut mapview);
+            if let Some(map_browser) = &mut gui.map_browser {
+                map_browser.release_click();
+                map_browser.scrollbar.release_scrollbar();
+            }
+            if let Some(stamp_browser) = &mut gui.stamp_browser {
+                stamp_browser.release_click();
+                stamp_browser.scrollbar.release_scrollbar();
+            }
             if gameinput.dialog_button_press {
                 handle_dialog_input(&mut renderer,
+                                    &resource,
                                     &mut gameinput, 
                                     &mut gui,
+                                    &size,
+                                    scale,
                                     elwt,
                                     &mut editor_data,
                                     &mut mapview);
+                handle_stamp_browser_input(&mut renderer,
+                                    &resource,
+                                    &mut gameinput,
+                                    &mut gui,
+                                    &mut tileset,
+                                    &mut mapview);
+                handle_map_browser_input(&mut renderer,
+                                    &resource,
+                                    &mut gameinput,
+                                    &mut gui,
+                                    &size,
+                                    scale,
+                                    &mut editor_data,
+                                    &mut mapview);
             }
             gui.tileset_list.scrollbar.release_scrollbar();
             did_key_press[action_index(Action::Select)] = false;
         }
 
+        // Undo and Redo shortcuts, these only trigger once per key press
+        for (action, is_redo) in [(Action::Undo, false), (Action::Redo, true)] {
+            let index = action_index(action.clone());
+            if input_handler.is_action_down(&action) {
+                if !did_key_press[index] {
+                    did_key_press[index] = true;
+                    apply_map_history(&mut renderer, &resource, &mut gui, &mut mapview, &mut editor_data, is_redo);
+                }
+            } else {
+                did_key_press[index] = false;
+            }
+        }
+
+        // Clipboard shortcuts of the map selection
+        for action in [Action::Copy, Action::Cut, Action::Paste] {
+            let index = action_index(action.clone());
+            if input_handler.is_action_down(&action) {
+                if !did_key_press[index] {
+                    did_key_press[index] = true;
+                    handle_clipboard(&mut renderer, &resource, &mut gameinput, &mut gui, &mut mapview, &mut editor_data, action);
+                }
+            } else {
+                did_key_press[index] = false;
+            }
+        }
+
+        if gameinput.open_map_browser {
+            gameinput.open_map_browser = false;
+            open_map_browser(&mut renderer, &resource, &mut gui, &mut graphics.image_atlas, &size, scale, &mut editor_data, &mapview);
+        }
+
+        // World view shortcut, this opens the view or closes it when it is already open
+        let index = action_index(Action::WorldView);
+        if input_handler.is_action_down(&Action::WorldView) {
+            if !did_key_press[index] {
+                did_key_press[index] = true;
+                if gui.world_view.is_some() {
+                    gui.world_view = None;
+                } else {
+                    open_world_view(&mut renderer, &resource, &mut gui, &mut graphics.image_atlas, &size, scale, &mut editor_data, &mapview);
+                }
+            }
+        } else {
+            did_key_press[index] = false;
+        }
+        // Stamp library shortcut, this opens the list of stamps or closes it when it is already open
+        let index = action_index(Action::StampLibrary);
+        if input_handler.is_action_down(&Action::StampLibrary) {
+            if !did_key_press[index] {
+                did_key_press[index] = true;
+                if gui.stamp_browser.is_some() {
+                    gui.stamp_browser = None;
+                } else {
+                    open_stamp_browser(&mut renderer, &resource, &mut gui, &size, scale);
+                }
+            }
+        } else {
+            did_key_press[index] = false;
+        }
+        let wheel = input_handler.mouse_wheel_value(MouseAxis::Vertical);
+        if wheel != 0.0 {
+            let screen_pos = Vec2::new(mouse_pos.0 / ZOOM_LEVEL, (size.height - mouse_pos.1) / ZOOM_LEVEL);
+            if let Some(world_view) = &mut gui.world_view {
+                world_view.zoom_at(screen_pos, wheel);
+            } else {
+                adjust_tile_weight(&mut renderer, &mut gui, &mut tileset, screen_pos, wheel);
+            }
+        }
+
         let seconds = frame_time.seconds();
         // update our systems data to the gpu. this is the Camera in the shaders.
         graphics.system.update(&renderer, &frame_time);
@@ -399,6 +556,7 @@ async fn main() -> Result<(), AscendingError> {
         graphics.map_renderer.map_update(&mut tileset.map, &mut renderer); // Tileset
         graphics.image_renderer.image_update(&mut tileset.selection, &mut renderer, &mut graphics.image_atlas); // Tileset Selection
         // Map View
+        mapview.sync_maps();
         mapview.maps.iter_mut().for_each(|map| {
             graphics.map_renderer.map_update(map, &mut renderer);
         });
@@ -406,6 +564,18 @@ async fn main() -> Result<(), AscendingError> {
             graphics.image_renderer.image_update(image, &mut renderer, &mut graphics.image_atlas);
         });
         graphics.image_renderer.image_update(&mut mapview.selection_preview, &mut renderer, &mut graphics.image_atlas);
+        if mapview.selection.is_some() {
+            graphics.ui_renderer.rect_update(&mut mapview.selection_marker, &mut renderer, &mut graphics.ui_atlas);
+        }
+        if gui.current_setting_tab == TAB_ATTRIBUTE {
+            mapview.update_attribute_overlay(&resource, &mut renderer, &size, scale);
+            mapview.attribute_overlay.values_mut().for_each(|overlay| {
+                graphics.image_renderer.image_update(&mut overlay.bg, &mut renderer, &mut graphics.image_atlas);
+                graphics.text_renderer
+                    .text_update(&mut overlay.text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+        }
         // GUI
         graphics.image_renderer.image_update(&mut gui.bg_layout, &mut renderer, &mut graphics.image_atlas);
         gui.buttons.iter_mut().for_each(|button| {
@@ -420,8 +590,37 @@ async fn main() -> Result<(), AscendingError> {
                         .unwrap();
                 }
             },
-            TAB_ATTRIBUTE => {},
-            TAB_PROPERTIES => {},
+            TAB_ATTRIBUTE => {
+                let setting = &mut gui.attribute_setting;
+                setting.options.iter_mut().for_each(|option| {
+                    graphics.image_renderer.image_update(&mut option.button, &mut renderer, &mut graphics.image_atlas);
+                    graphics.text_renderer
+                        .text_update(&mut option.text, &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                });
+                for index in 0..setting.field_count() {
+                    graphics.text_renderer
+                        .text_update(&mut setting.field_labels[index], &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                    graphics.ui_renderer.rect_update(&mut setting.field_boxes[index], &mut renderer, &mut graphics.ui_atlas);
+                    graphics.text_renderer
+                        .text_update(&mut setting.field_texts[index], &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                }
+            },
+            TAB_PROPERTIES => {
+                gui.property_setting.labels.iter_mut().for_each(|label| {
+                    graphics.text_renderer
+                        .text_update(label, &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                });
+                gui.property_setting.boxes.iter_mut().for_each(|textbox| {
+                    graphics.ui_renderer.rect_update(&mut textbox.rect, &mut renderer, &mut graphics.ui_atlas);
+                    graphics.text_renderer
+                        .text_update(&mut textbox.text, &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                });
+            },
             _ => {},
         }
         // Tileset List
@@ -441,6 +640,89 @@ async fn main() -> Result<(), AscendingError> {
         }
 
         // Dialog
+        if let Some(world_view) = &mut gui.world_view {
+            graphics.dialog_renderer.image_update(&mut world_view.bg, &mut renderer, &mut graphics.image_atlas);
+            graphics.ui_renderer.rect_update(&mut world_view.window, &mut renderer, &mut graphics.ui_atlas);
+            graphics.dialog_text_renderer
+                .text_update(&mut world_view.title, &mut graphics.text_atlas, &mut renderer)
+                .unwrap();
+            graphics.dialog_text_renderer
+                .text_update(&mut world_view.hover_label, &mut graphics.text_atlas, &mut renderer)
+                .unwrap();
+            world_view.thumbnails.iter_mut().filter(|thumbnail| thumbnail.visible).for_each(|thumbnail| {
+                graphics.dialog_renderer.image_update(&mut thumbnail.image, &mut renderer, &mut graphics.image_atlas);
+                graphics.ui_renderer.rect_update(&mut thumbnail.marker, &mut renderer, &mut graphics.ui_atlas);
+            });
+        }
+        if let Some(map_browser) = &mut gui.map_browser {
+            graphics.dialog_renderer.image_update(&mut map_browser.bg, &mut renderer, &mut graphics.image_atlas);
+            graphics.ui_renderer.rect_update(&mut map_browser.window, &mut renderer, &mut graphics.ui_atlas);
+            [&mut map_browser.message, &mut map_browser.count_label].into_iter().for_each(|text| {
+                graphics.dialog_text_renderer
+                    .text_update(text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            map_browser.list_box.iter_mut().chain(map_browser.filter_boxes.iter_mut()).for_each(|rect| {
+                graphics.ui_renderer.rect_update(rect, &mut renderer, &mut graphics.ui_atlas);
+            });
+            map_browser.filter_labels.iter_mut().chain(map_browser.filter_texts.iter_mut()).for_each(|text| {
+                graphics.dialog_text_renderer
+                    .text_update(text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            let visible_rows = map_browser.visible_rows();
+            map_browser.rows.iter_mut().take(visible_rows).for_each(|rect| {
+                graphics.ui_renderer.rect_update(rect, &mut renderer, &mut graphics.ui_atlas);
+            });
+            map_browser.thumbnails.iter_mut().take(visible_rows).for_each(|image| {
+                graphics.dialog_renderer.image_update(image, &mut renderer, &mut graphics.image_atlas);
+            });
+            map_browser.row_texts.iter_mut().take(visible_rows).for_each(|text| {
+                graphics.dialog_text_renderer
+                    .text_update(text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            map_browser.buttons.iter_mut().for_each(|dialogbutton| {
+                graphics.dialog_renderer.image_update(&mut dialogbutton.image, &mut renderer, &mut graphics.image_atlas);
+                graphics.dialog_text_renderer
+                    .text_update(&mut dialogbutton.text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            map_browser.scrollbar.images.iter_mut().for_each(|image| {
+                graphics.dialog_renderer.image_update(image, &mut renderer, &mut graphics.image_atlas);
+            });
+        }
+        if let Some(stamp_browser) = &mut gui.stamp_browser {
+            graphics.dialog_renderer.image_update(&mut stamp_browser.bg, &mut renderer, &mut graphics.image_atlas);
+            graphics.ui_renderer.rect_update(&mut stamp_browser.window, &mut renderer, &mut graphics.ui_atlas);
+            [&mut stamp_browser.message, &mut stamp_browser.count_label, &mut stamp_browser.name_label, &mut stamp_browser.name_text]
+                .into_iter().for_each(|text| {
+                    graphics.dialog_text_renderer
+                        .text_update(text, &mut graphics.text_atlas, &mut renderer)
+                        .unwrap();
+                });
+            stamp_browser.list_box.iter_mut().chain(std::iter::once(&mut stamp_browser.name_box)).for_each(|rect| {
+                graphics.ui_renderer.rect_update(rect, &mut renderer, &mut graphics.ui_atlas);
+            });
+            let visible_rows = stamp_browser.visible_rows();
+            stamp_browser.rows.iter_mut().take(visible_rows).for_each(|rect| {
+                graphics.ui_renderer.rect_update(rect, &mut renderer, &mut graphics.ui_atlas);
+            });
+            stamp_browser.row_texts.iter_mut().take(visible_rows).for_each(|text| {
+                graphics.dialog_text_renderer
+                    .text_update(text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            stamp_browser.buttons.iter_mut().for_each(|dialogbutton| {
+                graphics.dialog_renderer.image_update(&mut dialogbutton.image, &mut renderer, &mut graphics.image_atlas);
+                graphics.dialog_text_renderer
+                    .text_update(&mut dialogbutton.text, &mut graphics.text_atlas, &mut renderer)
+                    .unwrap();
+            });
+            stamp_browser.scrollbar.images.iter_mut().for_each(|image| {
+                graphics.dialog_renderer.image_update(image, &mut renderer, &mut graphics.image_atlas);
+            });
+        }
         if let Some(dialog) = &mut gui.dialog {
             graphics.dialog_renderer.image_update(&mut dialog.bg, &mut renderer, &mut graphics.image_atlas);
             graphics.ui_renderer.rect_update(&mut dialog.window, &mut renderer, &mut graphics.ui_atlas);
@@ -466,7 +748,7 @@ async fn main() -> Result<(), AscendingError> {
                             .text_update(text, &mut graphics.text_atlas, &mut renderer)
                             .unwrap();
             });
-            if dialog.dialog_type == DialogType::TypeMapSave {
+            if dialog.is_list() {
                 dialog.scrollbar.images.iter_mut().for_each(|image| {
                     graphics.image_renderer.image_update(image, &mut renderer, &mut graphics.image_atlas);
                 });
@@ -513,6 +795,16 @@ async fn main() -> Result<(), AscendingError> {
         }
         fps += 1;
 
+        // Keep a copy of the unsaved maps in case the editor does not close properly
+        if autosave_time < seconds {
+            if editor_data.got_changes() {
+                if let Err(e) = editor_data.autosave_maps(&resource, &mapview.document) {
+                    error!("Failed to autosave the maps, Err {:?}", e);
+                }
+            }
+            autosave_time = seconds + AUTOSAVE_INTERVAL;
+        }
+
         input_handler.end_frame();
         frame_time.update();
         renderer.present().unwrap();
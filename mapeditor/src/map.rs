@@ -1,21 +1,44 @@
 use graphics::*;
+use cosmic_text::{Attrs, Metrics};
+use winit::dpi::PhysicalSize;
 use crate::resource::*;
-use crate::collection::TEXTURE_SIZE;
+use crate::collection::{TEXTURE_SIZE, ZOOM_LEVEL};
+use crate::map_data::*;
 use indexmap::IndexMap;
 
-mod recording;
+// The colored tile and glyph that show the attribute of a tile on the main map
+pub struct AttributeOverlay {
+    pub bg: Image,
+    pub text: Text,
+}
+
+// A rectangle of the main map that was picked with the selection tool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapSelection {
+    pub pos: Vec2,
+    pub size: Vec2,
+}
 
-use recording::*;
+impl MapSelection {
+    pub fn contains(&self, tile_pos: Vec2) -> bool {
+        tile_pos.x >= self.pos.x && tile_pos.x < self.pos.x + self.size.x
+            && tile_pos.y >= self.pos.y && tile_pos.y < self.pos.y + self.size.y
+    }
+}
 
+// The GPU maps only show the tiles of the document, every change must be done on the document
 pub struct MapView {
+    pub document: MapDocument,
     pub maps: Vec<Map>,
     pub link_map_selection: Vec<Image>,
     pub selection_preview: Image,
     preview_pos: Vec2,
     preview_size: Vec2,
+    pub selection: Option<MapSelection>,
+    pub selection_marker: Rect,
 
-    // Recording
-    pub record: Records,
+    // The overlay only exist on tiles that are not walkable
+    pub attribute_overlay: IndexMap<usize, AttributeOverlay>,
 }
 
 impl MapView {
@@ -78,13 +101,31 @@ impl MapView {
         selection_preview.uv = Vec4::new(2.0, 2.0, 17.0, 17.0);
         selection_preview.color = Color::rgba(0, 0, 150, 150);
 
+        // The border of the map selection, this is only shown while there is a selection
+        let mut selection_marker = Rect::new(renderer, 0);
+        selection_marker.set_color(Color::rgba(255, 255, 255, 30))
+            .set_border_width(2.0)
+            .set_border_color(Color::rgba(255, 255, 255, 220));
+
         Self {
+            document: MapDocument::new(),
             maps,
             link_map_selection,
             selection_preview,
             preview_pos: Vec2::new(0.0, 0.0),
             preview_size: Vec2::new(1.0, 1.0),
-            record: Records::new(),
+            selection: None,
+            selection_marker,
+            attribute_overlay: IndexMap::new(),
+        }
+    }
+
+    // Copy the tiles that changed on the document to the GPU maps
+    pub fn sync_maps(&mut self) {
+        for (map, grid) in self.maps.iter_mut().zip(self.document.maps.iter_mut()) {
+            for pos in grid.take_changes() {
+                map.set_tile(pos, grid.get_tile(pos));
+            }
         }
     }
 
@@ -114,96 +155,39 @@ impl MapView {
         result
     }
 
-    pub fn set_tile_group(&mut self, set_pos: Vec2, layer: u32, tileset: &Map, start_pos: Vec2, selection_size: Vec2) {
-        for x in 0..selection_size.x as u32 {
-            for y in 0..selection_size.y as u32 {
-                // We load the tile data from the tileset
-                let tiledata = tileset.get_tile((start_pos.x as u32 + x, start_pos.y as u32 + y, 0));
-
-                // Make sure we only add tile that are not empty
-                if tiledata.texture_id > 0 {
-                    // Make sure we wont set map outside the map size limit
-                    if (set_pos.x as u32 + x) < 32 && (set_pos.y as u32 + y) < 32 {
-                        // Record change for undo purpose
-                        let last_texture = self.maps[0].get_tile((set_pos.x as u32 + x, set_pos.y as u32 + y, layer)).texture_id;
-                        self.record.push_change(Vec3::new(set_pos.x + x as f32, set_pos.y + y as f32, layer as f32), last_texture as i32);
-
-                        self.maps[0].set_tile((set_pos.x as u32 + x, set_pos.y as u32 + y, layer), tiledata);
-                    }
+    // Update the overlay of the attributes that have been changed since the last update
+    pub fn update_attribute_overlay(&mut self, resource: &TextureAllocation, renderer: &mut GpuRenderer, size: &PhysicalSize<f32>, scale: f64) {
+        for index in self.document.take_changed_attributes() {
+            let attribute = self.document.attributes[index];
+            if attribute == MapAttribute::Walkable {
+                if let Some(overlay) = self.attribute_overlay.swap_remove(&index) {
+                    renderer.remove_buffer(overlay.bg.store_id);
+                    renderer.remove_buffer(overlay.text.store_id);
                 }
+                continue;
             }
-        }
-    }
-
-    pub fn delete_tile_group(&mut self, set_pos: Vec2, layer: u32, size: Vec2) {
-        for x in 0..size.x as u32 {
-            for y in 0..size.y as u32 {
-                // Make sure we wont set map outside the map size limit
-                if (set_pos.x as u32 + x) < 32 && (set_pos.y as u32 + y) < 32 {
-                    let texture_id = self.maps[0].get_tile((set_pos.x as u32 + x, set_pos.y as u32 + y, layer)).texture_id;
-                    if texture_id > 0 {
-                        // Record change for undo purpose
-                        let last_texture = self.maps[0].get_tile((set_pos.x as u32 + x, set_pos.y as u32 + y, layer)).texture_id;
-                        self.record.push_change(Vec3::new(set_pos.x + x as f32, set_pos.y + y as f32, layer as f32), last_texture as i32);
-                        
-                        self.maps[0].set_tile(
-                            (set_pos.x as u32 + x, set_pos.y as u32 + y, layer), 
-                            TileData::default());
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn set_tile_fill(&mut self, set_pos: Vec2, layer: u32, tileset: &Map, tileset_pos: Vec2) {
-        // Get the tile data from the tileset
-        let tiledata = tileset.get_tile((tileset_pos.x as u32, tileset_pos.y as u32, 0));
-        if tiledata.texture_id == 0 {
-            return;
-        }
-
-        // We will only change the tiles that have a similar texture id, and this will be use to check
-        let comparedata = self.maps[0].get_tile((set_pos.x as u32, set_pos.y as u32, layer)).texture_id;
-        if comparedata == tiledata.texture_id {
-            return;
-        }
 
-        // This will hold the location that need to be paint
-        let mut paint_to_map: Vec<Vec2> = Vec::with_capacity(0);
-
-        // Place our starting location on to be paint collection
-        paint_to_map.push(set_pos);
-
-        // Loop through our collections of position that requires to be paint
-        while let Some(pos) = paint_to_map.pop() {
-            // Record change for undo purpose
-            let last_texture = self.maps[0].get_tile((pos.x as u32, pos.y as u32, layer)).texture_id;
-            self.record.push_change(Vec3::new(pos.x, pos.y, layer as f32), last_texture as i32);
-
-            // Paint the map
-            self.maps[0].set_tile((pos.x as u32, pos.y as u32, layer), tiledata);
-            
-            // Check direction
-            for dir in 0..4 {
-                // Get the surrounding map position
-                let mut adjust_pos = Vec2::new(0.0, 0.0);
-                match dir {
-                    1 => { adjust_pos.y = 1.0; }, // Up
-                    2 => { adjust_pos.x = -1.0; }, // Left
-                    3 => { adjust_pos.x = 1.0; }, // Right
-                    _ => { adjust_pos.y = -1.0; }, // Down
-                }
-                let checkpos = pos + adjust_pos;
-
-                if checkpos.x >= 0.0 && checkpos.x < 32.0 && checkpos.y >= 0.0 && checkpos.y < 32.0 {
-                    // Check the map texture id and we make sure that we only change
-                    // if they have the same texture id as the starting tile
-                    let check_data = self.maps[0].get_tile((checkpos.x as u32, checkpos.y as u32, layer)).texture_id;
-                    if check_data == comparedata {
-                        paint_to_map.push(checkpos);
-                    }
-                }
-            }
+            let pos = Vec2::new(self.maps[0].pos.x + (index % 32) as f32 * TEXTURE_SIZE as f32,
+                                self.maps[0].pos.y + (index / 32) as f32 * TEXTURE_SIZE as f32);
+            let overlay = self.attribute_overlay.entry(index).or_insert_with(|| {
+                let mut bg = Image::new(Some(resource.white.allocation), renderer, 1);
+                bg.pos = Vec3::new(pos.x, pos.y, 1.6);
+                bg.hw = Vec2::new(TEXTURE_SIZE as f32, TEXTURE_SIZE as f32);
+                bg.uv = Vec4::new(2.0, 2.0, 17.0, 17.0);
+
+                let mut text = Text::new(
+                    renderer,
+                    Some(Metrics::new(16.0, 16.0).scale(scale as f32)),
+                    Vec3::new((pos.x + 5.0) * ZOOM_LEVEL, (pos.y + 1.0) * ZOOM_LEVEL, 1.5), Vec2::new(TEXTURE_SIZE as f32, TEXTURE_SIZE as f32), 1.0
+                );
+                text.set_buffer_size(renderer, size.width as i32, size.height as i32)
+                    .set_bounds(Some(Bounds::new(pos.x * ZOOM_LEVEL, pos.y * ZOOM_LEVEL, (pos.x + TEXTURE_SIZE as f32) * ZOOM_LEVEL, (pos.y + TEXTURE_SIZE as f32) * ZOOM_LEVEL)))
+                    .set_default_color(Color::rgba(255, 255, 255, 255));
+                AttributeOverlay { bg, text }
+            });
+            overlay.bg.color = attribute.color();
+            overlay.bg.changed = true;
+            overlay.text.set_text(renderer, attribute.glyph(), Attrs::new());
         }
     }
 
@@ -218,22 +202,33 @@ impl MapView {
         }
     }
     
+    pub fn get_preview_pos(&self) -> Vec2 {
+        self.preview_pos
+    }
+
+    // Select the rectangle between two tiles, the selection always stays within the main map
+    pub fn set_selection(&mut self, start: Vec2, end: Vec2) {
+        let min_pos = start.min(end).clamp(Vec2::ZERO, Vec2::splat(31.0));
+        let max_pos = start.max(end).clamp(Vec2::ZERO, Vec2::splat(31.0));
+        let selection = MapSelection { pos: min_pos, size: max_pos - min_pos + 1.0 };
+
+        self.selection_marker.set_position(Vec3::new(self.maps[0].pos.x + selection.pos.x * TEXTURE_SIZE as f32,
+                                                    self.maps[0].pos.y + selection.pos.y * TEXTURE_SIZE as f32,
+                                                    3.9))
+            .set_size(selection.size * TEXTURE_SIZE as f32);
+        self.selection = Some(selection);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
     pub fn change_selection_preview_size(&mut self, size: Vec2) {
         self.preview_size = size;
         self.adjust_selection_preview();
         self.selection_preview.changed = true;
     }
 
-    pub fn clear_map(&mut self, index: usize) {
-        (0..8).for_each(|layer| {
-            (0..32).for_each(|x| {
-                (0..32).for_each(|y| {
-                    self.maps[index].set_tile((x, y, layer), TileData::default());
-                });
-            });
-        });
-    }
-
     // This function ensure that the selection preview does not show outside the map boundary
     fn adjust_selection_preview(&mut self) {
         let max_size = Vec2::new(32.0, 32.0);
@@ -245,25 +240,4 @@ impl MapView {
 
         self.selection_preview.hw = Vec2::new(new_size.x * TEXTURE_SIZE as f32, new_size.y * TEXTURE_SIZE as f32);
     }
-
-    pub fn apply_undo(&mut self) {
-        if self.record.data.len() > 0 {
-            let get_change = self.record.get_last_change();
-            if get_change.is_none() {
-                return;
-            }
-            let data = get_change.unwrap();
-            for (_key, changedata) in data.changes.iter() {
-                let pos = Vec3::new(changedata.pos.x, changedata.pos.y, changedata.pos.z);
-                let texture_id = changedata.texture_id as u32;
-                
-                self.maps[0].set_tile((pos.x as u32, pos.y as u32, pos.z as u32),
-                                TileData {
-                                    texture_id: texture_id,
-                                    texture_layer: 0,
-                                    color: Color::rgba(255, 255, 255, 255),
-                                });
-            }
-        }
-    }
 }
\ No newline at end of file
@@ -1,386 +1,402 @@
 use graphics::*;
 use serde::{Deserialize, Serialize};
-use std::fs::OpenOptions;
-use std::io::BufReader;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
 use std::path::Path;
-use indexmap::IndexMap;
-
-use crate::map::*;
-
-#[derive(Debug)]
-pub enum Direction {
-    North,
-    South,
-    East,
-    West,
-    NorthEast,
-    NorthWest,
-    SouthEast,
-    SouthWest,
+use log::{error, warn};
+
+mod attribute;
+mod clipboard;
+mod document;
+mod editor;
+mod format;
+mod migration;
+mod palette;
+mod properties;
+mod recording;
+mod recovery;
+mod render;
+mod shape;
+mod stamp;
+mod terrain;
+mod tiled;
+mod tilesheet;
+#[cfg(test)]
+pub(crate) mod test_support;
+
+pub use attribute::*;
+pub use clipboard::*;
+pub use document::*;
+pub use editor::*;
+pub use format::*;
+pub use migration::*;
+pub use palette::*;
+pub use properties::*;
+pub use recording::*;
+pub use recovery::*;
+pub use render::*;
+pub use shape::*;
+pub use stamp::*;
+pub use terrain::*;
+pub use tiled::*;
+pub use tilesheet::*;
+
+pub const MAP_PATH: &str = "./data/maps/";
+// Size of a tile in pixels on the tilesheets and the map
+pub const TILE_SIZE: u32 = 20;
+
+// Amount of backup copies that are kept for each map, the newest copy is .bak1
+const MAX_BACKUP: usize = 3;
+
+// A tile is stored by the tilesheet it came from and its index within that tilesheet,
+// so saved maps are not affected by the order or layout of the texture atlas
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TileRef {
+    // Index on the map's tilesheet table
+    pub sheet: u32,
+    // Local tile index within the tilesheet
+    pub index: u32,
 }
 
-pub struct EditorData {
-    // Map ID
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    pub id: Vec<Option<TileRef>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapData {
+    pub version: u32,
     pub x: i32,
     pub y: i32,
     pub group: u64,
-
-    // Loaded Maps
-    pub current_index: String,
-    pub maps: IndexMap<String, MapData>,
-    pub did_map_change: IndexMap<String, bool>,
+    pub tilesheets: Vec<String>,
+    pub tile: Vec<Tile>,
+    #[serde(default = "default_attributes")]
+    pub attribute: Vec<MapAttribute>,
+    #[serde(default)]
+    pub properties: MapProperties,
 }
 
-impl EditorData {
-    pub fn new() -> Result<EditorData, AscendingError> {
-        let mut maps = IndexMap::new();
-        let mut did_map_change = IndexMap::new();
-
-        let current_index = format!("{}_{}_{}", 0, 0, 0);
-        let map = load_file(0, 0, 0)?;
-        maps.insert("0_0_0".to_string(), map);
-        did_map_change.insert("0_0_0".to_string(), false);
-
-        Ok(Self {
-            x: 0,
-            y: 0,
-            group: 0,
-            current_index,
-            maps,
-            did_map_change,
-        })
+impl MapData {
+    pub fn default(x: i32, y: i32, group: u64) -> Self {
+        Self {
+            version: MAP_VERSION,
+            x,
+            y,
+            group,
+            tilesheets: Vec::new(),
+            tile: vec![Tile { id: vec![None; 1024] }; 8],
+            attribute: default_attributes(),
+            properties: MapProperties::default(),
+        }
     }
 
-    pub fn init_map(&mut self, x: i32, y: i32, group: u64) {
-        // Set position
-        self.x = x;
-        self.y = y;
-        self.group = group;
-        let key_data = format!("{}_{}_{}", x, y, group);
-        
-        if self.maps.contains_key(&key_data) {
-            // Since the map is already loaded, we just switch the center map
-            self.current_index = key_data;
-        } else {
-            // Change current center map
-            self.current_index = key_data;
-            // Since the map is not loaded, we must load the file and add it on the loaded maps
-            let map = load_file(self.x, self.y, self.group).unwrap();
-            self.maps.insert(self.current_index.clone(), map);
-            self.did_map_change.insert(self.current_index.clone(), false);
-        }
+    // Returns the tilesheet name and local tile index placed on this tile
+    pub fn get_tile_ref(&self, layer: usize, tile_num: usize) -> Option<(&str, u32)> {
+        let tile_ref = self.tile[layer].id[tile_num]?;
+        let name = self.tilesheets.get(tile_ref.sheet as usize)?;
+        Some((name.as_str(), tile_ref.index))
     }
 
-    pub fn move_map(&mut self, direction: Direction) -> Option<String> {
-        match direction {
-            Direction::East => { self.x += 1; },
-            Direction::North => { self.y += 1; },
-            Direction::South => { self.y -= 1; },
-            Direction::West => { self.x -= 1; },
-            Direction::NorthEast => { self.x += 1; self.y += 1; },
-            Direction::NorthWest => { self.x -= 1; self.y += 1; },
-            Direction::SouthEast => { self.x += 1; self.y -= 1; },
-            Direction::SouthWest => { self.x -= 1; self.y -= 1; },
-        }
-        let mut temp_key = None;
-
-        // Check if the current map has changes
-        if let Some(change) = self.did_map_change.get(&self.current_index) {
-            // We will remove the map on the collection when no change has been done
-            if !change {
-                self.did_map_change.remove(&self.current_index);
-                self.maps.remove(&self.current_index);
-            } else {
-                temp_key = Some(self.current_index.clone());
+    pub fn set_tile_ref(&mut self, layer: usize, tile_num: usize, name: &str, tile_index: u32) {
+        let sheet = match self.tilesheets.iter().position(|sheet_name| sheet_name == name) {
+            Some(sheet) => sheet,
+            None => {
+                self.tilesheets.push(name.to_string());
+                self.tilesheets.len() - 1
             }
-        }
+        };
+        self.tile[layer].id[tile_num] = Some(TileRef { sheet: sheet as u32, index: tile_index });
+    }
 
-        let key_data = format!("{}_{}_{}", self.x, self.y, self.group);
-        if self.maps.contains_key(&key_data) {
-            // Since the map is already loaded, we just switch the center map
-            self.current_index = key_data;
-        } else {
-            // Change current center map
-            self.current_index = key_data;
-            // Since the map is not loaded, we must load the file and add it on the loaded maps
-            let map = load_file(self.x, self.y, self.group).unwrap();
-            self.maps.insert(self.current_index.clone(), map);
-            self.did_map_change.insert(self.current_index.clone(), false);
-        }
-        temp_key
+    // A map without tiles, attributes and properties, this is how a map that was never edited looks like
+    pub fn is_empty(&self) -> bool {
+        self.tile.iter().all(|tile| tile.id.iter().all(|tile_ref| tile_ref.is_none()))
+            && self.attribute.iter().all(|attribute| *attribute == MapAttribute::Walkable)
+            && self.properties == MapProperties::default()
     }
 
-    pub fn save_map_data(&mut self, map: &Map, old_map_key: Option<String>) {
-        // Check if the map should be save as file or temporary data
-        let (should_save, find_key);
-        if old_map_key.is_some() {
-            should_save = false;
-            find_key = old_map_key.unwrap();
-        } else {
-            should_save = true;
-            find_key = self.current_index.clone();
+    // Returns the problems found on the map, the map can be used as it is when this is empty
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.tile.len() != 8 {
+            problems.push(format!("Map has {} layers instead of 8", self.tile.len()));
         }
-        // This handles the copying of data from map tiles to map data
-        if let Some(mapdata) = self.maps.get_mut(&find_key) {
-            (0..8).for_each(|layer| {
-                (0..32).for_each(|x| {
-                    (0..32).for_each(|y| {
-                        let tile_num = get_tile_pos(x, y);
-                        mapdata.tile[layer].id[tile_num] = map.get_tile((x as u32, y as u32, layer as u32)).texture_id;
-                    });
-                });
-            });
-            if should_save {
-                mapdata.save_file().unwrap();
-                // Since we have saved the map, let's mark the map as 'no change'
-                if let Some(did_change) = self.did_map_change.get_mut(&self.current_index) {
-                    *did_change = false;
-                }
+        for (layer, tile) in self.tile.iter().enumerate() {
+            if tile.id.len() != 1024 {
+                problems.push(format!("Layer {} has {} tiles instead of 1024", layer, tile.id.len()));
             }
+            let bad_refs = tile.id.iter().flatten()
+                .filter(|tile_ref| tile_ref.sheet as usize >= self.tilesheets.len())
+                .count();
+            if bad_refs > 0 {
+                problems.push(format!("Layer {} has {} tile/s with an unknown tilesheet", layer, bad_refs));
+            }
+        }
+        if self.attribute.len() != 1024 {
+            problems.push(format!("Map has {} attributes instead of 1024", self.attribute.len()));
         }
+        problems
     }
 
-    pub fn save_all_maps(&mut self) {
-        for (key, value) in self.did_map_change.iter() {
-            // Save only with changes
-            if *value {
-                if let Some(mapdata) = self.maps.get_mut(key) {
-                    mapdata.save_file().unwrap();
-                }
+    pub fn save_file(&self) -> Result<(), AscendingError> {
+        let format = map_format();
+        write_map_file(&map_file_name(self.x, self.y, self.group, format), self, format, true)?;
+
+        // The map could still have a file with the other format, it must not be loaded over the saved map
+        for other_format in MapFormat::LIST.iter().filter(|other_format| **other_format != format) {
+            let other_name = map_file_name(self.x, self.y, self.group, *other_format);
+            if Path::new(&other_name).exists() {
+                remove_map_file(&other_name)?;
             }
         }
+        Ok(())
     }
-    
-    pub fn load_map_data(&mut self, map: &mut MapView) {
-        // Clear the map before we start adding the tiles
-        map.clear_map(0);
-        // Add the tiles
-        if let Some(mapdata) = self.maps.get(&self.current_index) {
-            (0..8).for_each(|layer| {
-                (0..32).for_each(|x| {
-                    (0..32).for_each(|y| {
-                        let tile_num = get_tile_pos(x, y);
-                        let texture_id = mapdata.tile[layer].id[tile_num] as u32;
-                        if texture_id > 0 {
-                            map.maps[0].set_tile((x as u32, y as u32, layer as u32), 
-                                        TileData { 
-                                            texture_id,
-                                            texture_layer: 0,
-                                            color: Color::rgba(255, 255, 255, 255),
-                                        });
-                        }
-                    });
-                });
-            });
-        }
+}
+
+pub fn map_file_name(x: i32, y: i32, group: u64, format: MapFormat) -> String {
+    format!("{}{}_{}_{}.{}", MAP_PATH, x, y, group, format.extension())
+}
+
+pub fn create_file(x: i32, y: i32, group: u64, data: &MapData) -> Result<(), AscendingError> {
+    if is_map_exist(x, y, group) {
+        return Ok(());
     }
+    let format = map_format();
+    write_map_file(&map_file_name(x, y, group, format), data, format, true)
+}
 
-    pub fn load_link_maps(&mut self, map: &mut MapView) {
-        (0..8).for_each(|maplink| {
-            // Clear the map before we start adding the tiles
-            map.clear_map(maplink + 1);
-
-            // Set the map id, position for loading
-            let (start, size, key, x, y);
-            match maplink {
-                1 => { // Top
-                    x = self.x; y = self.y + 1;
-                    size = Vec2::new(32.0, 2.0);
-                    start = Vec2::new(0.0, 0.0);
-                },
-                2 => { // Top Right
-                    x = self.x + 1; y = self.y + 1;
-                    size = Vec2::new(2.0, 2.0);
-                    start = Vec2::new(0.0, 0.0);
-                },
-                3 => { // Left
-                    x = self.x - 1; y = self.y;
-                    size = Vec2::new(2.0, 32.0);
-                    start = Vec2::new(30.0, 0.0);
-                },
-                4 => { // Right
-                    x = self.x + 1; y = self.y;
-                    size = Vec2::new(2.0, 32.0);
-                    start = Vec2::new(0.0, 0.0);
-                },
-                5 => { // Bottom Left
-                    x = self.x - 1; y = self.y - 1;
-                    size = Vec2::new(2.0, 2.0);
-                    start = Vec2::new(30.0, 30.0);
-                },
-                6 => { // Bottom
-                    x = self.x; y = self.y - 1;
-                    size = Vec2::new(32.0, 2.0);
-                    start = Vec2::new(0.0, 30.0);
-                },
-                7 => { // Bottom Right
-                    x = self.x + 1; y = self.y - 1;
-                    size = Vec2::new(2.0, 2.0);
-                    start = Vec2::new(0.0, 30.0);
-                },
-                _ => { // Top Left
-                    x = self.x - 1; y = self.y + 1;
-                    size = Vec2::new(2.0, 2.0);
-                    start = Vec2::new(30.0, 0.0);
-                },
-            }
-            key = format!("{}_{}_{}", x, y, self.group);
-
-            // Let's check if map exist, and only load if map exist
-            if is_map_exist(x, y, self.group) {
-                // Check if map is already on our indexmap, otherwise we load it
-                if !self.maps.contains_key(&key) {
-                    // Since the map is not loaded, we must load the file and add it on the loaded maps
-                    let map = load_file(x, y, self.group).unwrap();
-                    self.maps.insert(key.clone(), map);
-                    self.did_map_change.insert(key.clone(), false);
-                }
-
-                // Add the tiles
-                if let Some(mapdata) = self.maps.get(&key) {
-                    (0..8).for_each(|layer| {
-                        (0..size.x as i32).for_each(|x| {
-                            (0..size.y as i32).for_each(|y| {
-                                let tile_num = get_tile_pos(start.x as i32 + x, start.y as i32 + y);
-                                let texture_id = mapdata.tile[layer].id[tile_num] as u32;
-                                
-                                if texture_id > 0 {
-                                    map.maps[maplink + 1].set_tile((x as u32, y as u32, layer as u32), 
-                                                TileData { 
-                                                    texture_id,
-                                                    texture_layer: 0,
-                                                    color: Color::rgba(255, 255, 255, 255),
-                                                });
-                                }
-                            });
-                        });
-                    });
-                }
-            }
-        });
+fn write_map_file(name: &str, data: &MapData, format: MapFormat, keep_backup: bool) -> Result<(), AscendingError> {
+    write_file_atomic(name, &encode_map(data, format)?, keep_backup)
+}
+
+// The file is written on a temporary file that will replace the file once it has been completely written,
+// so a crash or a full disk will never leave a half written file
+pub fn write_file_atomic(name: &str, bytes: &[u8], keep_backup: bool) -> Result<(), AscendingError> {
+    let temp_name = format!("{}.tmp", name);
+
+    let mut file = match OpenOptions::new().write(true).create(true).truncate(true).open(&temp_name) {
+        Ok(file) => file,
+        Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to open {}, Err {:?}", temp_name, e)))),
+    };
+    if let Err(e) = file.write_all(bytes) {
+        let _ = fs::remove_file(&temp_name);
+        return Err(AscendingError::Other(OtherError::new(&format!("Failed to write {}, Err {:?}", temp_name, e))));
+    }
+    if let Err(e) = file.sync_all() {
+        let _ = fs::remove_file(&temp_name);
+        return Err(AscendingError::from(e));
     }
+    drop(file);
 
-    pub fn set_map_change(&mut self) -> bool {
-        if let Some(did_change) = self.did_map_change.get_mut(&self.current_index) {
-            *did_change = true;
-            return true;
+    if keep_backup {
+        backup_map_file(name)?;
+    }
+    fs::rename(&temp_name, name)?;
+
+    // Make sure the rename is written on the disk, this is not supported on every platform
+    if let Some(folder) = Path::new(name).parent() {
+        if let Ok(dir) = File::open(folder) {
+            let _ = dir.sync_all();
         }
-        false
     }
+    Ok(())
+}
 
-    pub fn got_changes(&mut self) -> bool {
-        self.did_map_change.values().any(|&value| value)
+// Copy the current map file as .bak1 and shift the older backups, the oldest one will be removed
+fn backup_map_file(name: &str) -> Result<(), AscendingError> {
+    if MAX_BACKUP == 0 || !Path::new(name).exists() {
+        return Ok(());
     }
 
-    pub fn did_change(&mut self, x: i32, y: i32, group: u64) -> bool {
-        let key_data = format!("{}_{}_{}", x, y, group);
-        if !self.did_map_change.contains_key(&key_data) {
-            return false;
+    for index in (1..MAX_BACKUP).rev() {
+        let backup_name = format!("{}.bak{}", name, index);
+        if Path::new(&backup_name).exists() {
+            fs::rename(&backup_name, format!("{}.bak{}", name, index + 1))?;
         }
-        *self.did_map_change.get(&key_data).unwrap()
     }
+    fs::copy(name, format!("{}.bak1", name))?;
+    Ok(())
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Tile {
-    pub id: Vec<u32>,
+// The removed map file is kept as .bak1 like a saved map
+fn remove_map_file(name: &str) -> Result<(), AscendingError> {
+    backup_map_file(name)?;
+    fs::remove_file(name)?;
+    Ok(())
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct MapData {
-    pub x: i32,
-    pub y: i32,
-    pub group: u64,
-    pub tile: Vec<Tile>,
-}
+// Returns the map and the version it was saved with
+// A map that does not exist is only created in memory, its file is written once the map is saved with content
+pub fn load_file(x: i32, y: i32, group: u64) -> Result<(MapData, u32), AscendingError> {
+    if !is_map_exist(x, y, group) {
+        return Ok((MapData::default(x, y, group), MAP_VERSION));
+    }
 
-impl MapData {
-    pub fn default(x: i32, y: i32, group: u64) -> Self {
-        Self {
-            x,
-            y,
-            group,
-            tile: vec![Tile { id: vec![0; 1024] }; 8],
+    // The format of the project is checked first, so a map that exist on both format will use the project format
+    let (name, format) = match find_map_file(x, y, group) {
+        Some(found) => found,
+        None => return Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] could not be found", x, y, group)))),
+    };
+    match read_map_file(&name, format) {
+        Ok(data) => Ok(data),
+        Err(e) => {
+            // We must not replace a map that could not be read, otherwise the map will be lost once saved
+            error!("Failed to read {}, Err {:?}", name, e);
+            Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] could not be loaded, {}", x, y, group, e))))
         }
     }
+}
 
-    pub fn save_file(&self) -> Result<(), AscendingError> {
-        let name = format!("./data/maps/{}_{}_{}.json", self.x, self.y, self.group);
-
-        match OpenOptions::new().truncate(true).write(true).open(&name) {
-            Ok(file) => {
-                if let Err(e) = serde_json::to_writer_pretty(&file, self) {
-                    Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))))
-                } else {
-                    Ok(())
-                }
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
-            Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Failed to open {}, Err {:?}", name, e)))),
-        }
+// Read a map without creating its file, returns None when the map does not exist
+pub fn read_map(x: i32, y: i32, group: u64) -> Result<Option<MapData>, AscendingError> {
+    match find_map_file(x, y, group) {
+        Some((name, format)) => Ok(Some(read_map_file(&name, format)?.0)),
+        None => Ok(None),
     }
 }
 
-pub fn create_file(x: i32, y: i32, group: u64, data: &MapData) -> Result<(), AscendingError> {
-    let name = format!("./data/maps/{}_{}_{}.json", x, y, group);
-
-    match OpenOptions::new().write(true).create_new(true).open(&name) {
-        Ok(file) => {
-            if let Err(e) = serde_json::to_writer_pretty(&file, &data) {
-                Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))))
-            } else {
-                Ok(())
-            }
-        }
-        Err(ref e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
-        Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Failed to open {}, Err {:?}", name, e)))),
+// Read a map file of any location, returns the map and the version it was saved with
+pub fn read_map_file(name: &str, format: MapFormat) -> Result<(MapData, u32), AscendingError> {
+    let mut bytes = Vec::new();
+    match OpenOptions::new().read(true).open(name) {
+        Ok(mut file) => file.read_to_end(&mut bytes)?,
+        Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to open {}, Err {:?}", name, e)))),
+    };
+    decode_map(&bytes, format)
+}
+
+// List every map file on ./data/maps with its format, backups and temporary files are not included
+pub fn list_map_files() -> Result<Vec<(String, MapFormat)>, AscendingError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(MAP_PATH)? {
+        let path = entry?.path();
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => match MapFormat::LIST.iter().find(|map_format| map_format.extension() == ext) {
+                Some(map_format) => *map_format,
+                None => continue,
+            },
+            None => continue,
+        };
+        files.push((path.to_string_lossy().to_string(), format));
     }
+    files.sort();
+    Ok(files)
 }
 
-pub fn load_file(x: i32, y: i32, group: u64) -> Result<MapData, AscendingError> {
-    if !is_map_exist(x, y, group) {
-        let data = MapData::default(x,y,group);
-        match create_file(x, y, group, &MapData::default(x,y,group)) {
-            Ok(()) => return Ok(data),
-            Err(e) => return Err(e),
-        }
+// List the location of every map on ./data/maps, a map that exist on both format is only listed once
+pub fn list_map_locations() -> Result<Vec<(i32, i32, u64)>, AscendingError> {
+    let mut locations: Vec<(i32, i32, u64)> = list_map_files()?.iter()
+        .filter_map(|(name, _)| parse_map_key(Path::new(name).file_stem()?.to_str()?))
+        .collect();
+    locations.sort();
+    locations.dedup();
+    Ok(locations)
+}
+
+// Read the location of a map key that is formatted as x_y_group
+pub fn parse_map_key(key: &str) -> Option<(i32, i32, u64)> {
+    let mut parts = key.split('_');
+    let location = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    if parts.next().is_some() {
+        return None;
     }
+    Some(location)
+}
 
-    let name = format!("./data/maps/{}_{}_{}.json", x, y, group);
-    match OpenOptions::new().read(true).open(&name) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-
-            match serde_json::from_reader(reader) {
-                Ok(data) => Ok(data),
-                Err(e) => {
-                    println!("Error {:?}", e);
-                    Ok(MapData::default(x,y,group))
-                }
+// Remove the map files that has no content, the removed maps are kept as backup
+// Returns the name of the empty map files, nothing is removed on a dry run
+pub fn remove_empty_maps(dry_run: bool) -> Result<Vec<String>, AscendingError> {
+    let mut empty_maps = Vec::new();
+    for (name, format) in list_map_files()? {
+        let data = match read_map_file(&name, format) {
+            Ok((data, _)) => data,
+            Err(e) => {
+                // A map that could not be read is never removed
+                warn!("Failed to read {}, Err {:?}", name, e);
+                continue;
             }
+        };
+        if !data.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            remove_map_file(&name)?;
         }
-        Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Failed to open {}, Err {:?}", name, e)))),
+        empty_maps.push(name);
     }
+    Ok(empty_maps)
+}
+
+// Move the map to another location, the map on the old location is kept as backup
+pub fn move_map_file(from: (i32, i32, u64), to: (i32, i32, u64)) -> Result<(), AscendingError> {
+    let (name, format) = match find_map_file(from.0, from.1, from.2) {
+        Some(found) => found,
+        None => return Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] could not be found", from.0, from.1, from.2)))),
+    };
+    if is_map_exist(to.0, to.1, to.2) {
+        return Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] already exist", to.0, to.1, to.2))));
+    }
+
+    let (mut data, _) = read_map_file(&name, format)?;
+    data.x = to.0;
+    data.y = to.1;
+    data.group = to.2;
+    data.save_file()?;
+    remove_map_file(&name)
+}
+
+fn find_map_file(x: i32, y: i32, group: u64) -> Option<(String, MapFormat)> {
+    let format = map_format();
+    std::iter::once(format)
+        .chain(MapFormat::LIST.into_iter().filter(|other_format| *other_format != format))
+        .map(|format| (map_file_name(x, y, group, format), format))
+        .find(|(name, _)| Path::new(name).exists())
 }
 
 pub fn is_map_exist(x: i32, y: i32, group: u64) -> bool {
-    let name = format!("./data/maps/{}_{}_{}.json", x, y, group);
-    Path::new(&name).exists()
+    find_map_file(x, y, group).is_some()
 }
 
 pub fn get_tile_pos(x: i32, y: i32) -> usize {
     (x + (y * 32 as i32)) as usize
 }
 
-pub fn convert_to_dir(dir: usize) -> Direction {
-    match dir {
-        1 => { Direction::North },
-        2 => { Direction::NorthEast },
-        3 => { Direction::West },
-        4 => { Direction::East },
-        5 => { Direction::SouthWest },
-        6 => { Direction::South },
-        7 => { Direction::SouthEast },
-        _ => { Direction::NorthWest },
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_map_is_empty() {
+        assert!(MapData::default(0, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn map_with_tile_is_not_empty() {
+        let mut data = MapData::default(0, 0, 0);
+        data.set_tile_ref(3, 100, "tile_0.png", 5);
+        assert!(!data.is_empty());
+    }
+
+    #[test]
+    fn map_with_attribute_or_property_is_not_empty() {
+        let mut data = MapData::default(0, 0, 0);
+        data.attribute[10] = MapAttribute::Blocked;
+        assert!(!data.is_empty());
+
+        let mut data = MapData::default(0, 0, 0);
+        data.properties.name = "Town".to_string();
+        assert!(!data.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn binary_map_with_oversized_header_is_rejected() {
+        let mut bytes = encode_map(&MapData::default(0, 0, 0), MapFormat::Binary).unwrap();
+        assert!(decode_map(&bytes, MapFormat::Binary).is_ok());
+
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_map(&bytes, MapFormat::Binary).is_err());
+        bytes[8..12].copy_from_slice(&1u32.to_le_bytes());
+        assert!(decode_map(&bytes, MapFormat::Binary).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_ATTRIBUTE: usize = 5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarpData {
+    pub map_x: i32,
+    pub map_y: i32,
+    pub map_group: u64,
+    pub tile_x: u32,
+    pub tile_y: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapAttribute {
+    #[default]
+    Walkable,
+    Blocked,
+    Warp(WarpData),
+    Item { id: u32, quantity: u16 },
+    NpcSpawn { id: u32 },
+    Door { key: u32 },
+}
+
+impl MapAttribute {
+    // The attribute index is the position of the attribute on the attribute tab
+    pub fn as_str<'a>(index: usize) -> &'a str {
+        match index {
+            0 => "Blocked",
+            1 => "Warp",
+            2 => "Item",
+            3 => "NPC Spawn",
+            _ => "Door",
+        }
+    }
+
+    pub fn get_index(&self) -> Option<usize> {
+        match self {
+            MapAttribute::Walkable => None,
+            MapAttribute::Blocked => Some(0),
+            MapAttribute::Warp(_) => Some(1),
+            MapAttribute::Item { .. } => Some(2),
+            MapAttribute::NpcSpawn { .. } => Some(3),
+            MapAttribute::Door { .. } => Some(4),
+        }
+    }
+
+    // Name of the values that need to be entered for each attribute
+    pub fn field_names<'a>(index: usize) -> Vec<&'a str> {
+        match index {
+            1 => vec!["Map X", "Map Y", "Map Group", "Tile X", "Tile Y"],
+            2 => vec!["Item ID", "Quantity"],
+            3 => vec!["NPC ID"],
+            4 => vec!["Key ID"],
+            _ => vec![],
+        }
+    }
+
+    // Create the attribute from the values entered on the attribute tab
+    pub fn from_fields(index: usize, fields: &[String]) -> Self {
+        let value = |field: usize| -> i64 {
+            fields.get(field).and_then(|data| data.parse::<i64>().ok()).unwrap_or_default()
+        };
+        match index {
+            0 => MapAttribute::Blocked,
+            1 => MapAttribute::Warp(WarpData {
+                map_x: value(0) as i32,
+                map_y: value(1) as i32,
+                map_group: value(2).max(0) as u64,
+                tile_x: value(3).clamp(0, 31) as u32,
+                tile_y: value(4).clamp(0, 31) as u32,
+            }),
+            2 => MapAttribute::Item {
+                id: value(0).max(0) as u32,
+                quantity: value(1).clamp(0, u16::MAX as i64) as u16,
+            },
+            3 => MapAttribute::NpcSpawn { id: value(0).max(0) as u32 },
+            _ => MapAttribute::Door { key: value(0).max(0) as u32 },
+        }
+    }
+
+    pub fn glyph<'a>(&self) -> &'a str {
+        match self {
+            MapAttribute::Walkable => "",
+            MapAttribute::Blocked => "B",
+            MapAttribute::Warp(_) => "W",
+            MapAttribute::Item { .. } => "I",
+            MapAttribute::NpcSpawn { .. } => "N",
+            MapAttribute::Door { .. } => "D",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            MapAttribute::Walkable => Color::rgba(0, 0, 0, 0),
+            MapAttribute::Blocked => Color::rgba(200, 20, 20, 140),
+            MapAttribute::Warp(_) => Color::rgba(20, 80, 220, 140),
+            MapAttribute::Item { .. } => Color::rgba(20, 180, 40, 140),
+            MapAttribute::NpcSpawn { .. } => Color::rgba(220, 180, 20, 140),
+            MapAttribute::Door { .. } => Color::rgba(150, 60, 180, 140),
+        }
+    }
+}
+
+pub fn default_attributes() -> Vec<MapAttribute> {
+    vec![MapAttribute::Walkable; 1024]
+}
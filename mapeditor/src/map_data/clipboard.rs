@@ -0,0 +1,205 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use log::warn;
+
+use crate::map_data::*;
+
+// Used to recognize the map selections on the OS clipboard
+pub const CLIPBOARD_FORMAT: &str = "ascending_map_selection";
+
+// A tile is kept by its tilesheet name so it can be pasted on any map
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipboardTile {
+    pub sheet: String,
+    pub index: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipboardLayer {
+    pub layer: u32,
+    // Row by row starting from the bottom left tile of the selection, None is an empty tile
+    pub tiles: Vec<Option<ClipboardTile>>,
+}
+
+impl ClipboardLayer {
+    pub fn from_tile_stamp(resource: &impl TileSource, layer: u32, stamp: &TileStamp) -> Self {
+        let tiles = stamp.tiles.iter().map(|tiledata| {
+            resource.get_tile_ref(tiledata.texture_id, tiledata.texture_layer)
+                .map(|(sheet, index)| ClipboardTile { sheet: sheet.to_string(), index })
+        }).collect();
+        Self { layer, tiles }
+    }
+
+    // A tile that uses a tilesheet that does not exist becomes an empty tile
+    pub fn to_tile_stamp(&self, resource: &impl TileSource, size: Vec2) -> TileStamp {
+        let tiles = self.tiles.iter().map(|tile| match tile {
+            Some(tile) => resource.get_tile_data(&tile.sheet, tile.index).unwrap_or_else(|| {
+                warn!("Tile {} of {} could not be found", tile.index, tile.sheet);
+                TileData::default()
+            }),
+            None => TileData::default(),
+        }).collect();
+        TileStamp { size, tiles }
+    }
+}
+
+// The tiles of a map selection that were copied, the attributes are only kept when every layer was selected
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MapClipboard {
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<ClipboardLayer>,
+    #[serde(default)]
+    pub attributes: Option<Vec<MapAttribute>>,
+}
+
+impl MapClipboard {
+    pub fn copy(resource: &impl TileSource, document: &MapDocument, pos: Vec2, size: Vec2, layers: &[u32], with_attributes: bool) -> Self {
+        let layers = layers.iter()
+            .map(|layer| ClipboardLayer::from_tile_stamp(resource, *layer, &document.get_tile_group(pos, *layer, size)))
+            .collect();
+
+        let attributes = with_attributes.then(|| {
+            let mut attributes = Vec::with_capacity((size.x * size.y) as usize);
+            for y in 0..size.y as i32 {
+                for x in 0..size.x as i32 {
+                    attributes.push(document.get_attribute(pos + Vec2::new(x as f32, y as f32)));
+                }
+            }
+            attributes
+        });
+
+        Self {
+            format: CLIPBOARD_FORMAT.to_string(),
+            width: size.x as u32,
+            height: size.y as u32,
+            layers,
+            attributes,
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    // Place the tiles with the bottom left tile on pos, the part outside the main map is dropped
+    // A tile that uses a tilesheet that does not exist is pasted as an empty tile
+    pub fn paste(&self, resource: &impl TileSource, document: &mut MapDocument, pos: Vec2) {
+        for clipboard_layer in self.layers.iter() {
+            document.replace_tile_group(pos, clipboard_layer.layer, &clipboard_layer.to_tile_stamp(resource, self.size()));
+        }
+
+        if let Some(attributes) = &self.attributes {
+            for (index, attribute) in attributes.iter().enumerate() {
+                let offset = Vec2::new((index as u32 % self.width) as f32, (index as u32 / self.width) as f32);
+                document.set_attribute(pos + offset, *attribute);
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, AscendingError> {
+        serde_json::to_string(self)
+            .map_err(|e| AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))))
+    }
+
+    // Read a selection that was copied on the OS clipboard, the text could come from anywhere so it is checked first
+    pub fn from_json(text: &str) -> Result<Self, AscendingError> {
+        let clipboard: MapClipboard = serde_json::from_str(text)
+            .map_err(|e| AscendingError::Other(OtherError::new(&format!("The clipboard does not have a map selection, Err {:?}", e))))?;
+        if clipboard.format != CLIPBOARD_FORMAT {
+            return Err(AscendingError::Other(OtherError::new("The clipboard does not have a map selection")));
+        }
+        if clipboard.width == 0 || clipboard.width > 32 || clipboard.height == 0 || clipboard.height > 32 {
+            return Err(AscendingError::Other(OtherError::new(&format!("Map selection of {}x{} is not within 32x32",
+                clipboard.width, clipboard.height))));
+        }
+        let tile_count = (clipboard.width * clipboard.height) as usize;
+        if clipboard.layers.iter().any(|layer| layer.layer >= MapLayers::Count as u32 || layer.tiles.len() != tile_count)
+            || clipboard.attributes.as_ref().is_some_and(|attributes| attributes.len() != tile_count) {
+            return Err(AscendingError::Other(OtherError::new("Map selection does not match its size")));
+        }
+        Ok(clipboard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    fn clipboard(width: u32, height: u32) -> MapClipboard {
+        let tile = ClipboardTile { sheet: "tile_0.png".to_string(), index: 4 };
+        MapClipboard {
+            format: CLIPBOARD_FORMAT.to_string(),
+            width,
+            height,
+            layers: vec![ClipboardLayer { layer: 2, tiles: vec![Some(tile); (width * height) as usize] }],
+            attributes: Some(vec![MapAttribute::Blocked; (width * height) as usize]),
+        }
+    }
+
+    #[test]
+    fn copy_and_paste_on_another_position() {
+        let mut document = MapDocument::new();
+        document.maps[0].set_tile((1, 1, 0), tile(7));
+        document.set_attribute(Vec2::new(2.0, 1.0), MapAttribute::Blocked);
+
+        let copied = MapClipboard::copy(&TestTiles, &document, Vec2::new(1.0, 1.0), Vec2::new(2.0, 1.0), &[0], true);
+        assert_eq!(copied.layers[0].tiles[0], Some(ClipboardTile { sheet: "tile_0.png".to_string(), index: 7 }));
+        assert_eq!(copied.layers[0].tiles[1], None);
+
+        document.record().set_record();
+        copied.paste(&TestTiles, &mut document, Vec2::new(30.0, 5.0));
+        document.stop_records();
+        assert_eq!(document.maps[0].get_tile((30, 5, 0)).texture_id, 7);
+        assert_eq!(document.get_attribute(Vec2::new(31.0, 5.0)), MapAttribute::Blocked);
+
+        // The tiles outside the map are dropped
+        document.record().set_record();
+        copied.paste(&TestTiles, &mut document, Vec2::new(31.0, 8.0));
+        document.stop_records();
+        assert_eq!(document.maps[0].get_tile((31, 8, 0)).texture_id, 7);
+    }
+
+    #[test]
+    fn moving_a_selection_is_a_single_undo() {
+        let mut document = MapDocument::new();
+        document.maps[0].set_tile((0, 0, 0), tile(5));
+
+        // Each mouse move redo the whole move from the original tiles
+        document.record().set_record();
+        document.move_region(&TestTiles, Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(3.0, 0.0), &[0], false);
+        document.move_region(&TestTiles, Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(6.0, 2.0), &[0], false);
+        document.stop_records();
+
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 0);
+        assert_eq!(document.maps[0].get_tile((3, 0, 0)).texture_id, 0);
+        assert_eq!(document.maps[0].get_tile((6, 2, 0)).texture_id, 5);
+
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 5);
+        assert_eq!(document.maps[0].get_tile((6, 2, 0)).texture_id, 0);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let copied = clipboard(3, 2);
+        let pasted = MapClipboard::from_json(&copied.to_json().unwrap()).unwrap();
+        assert_eq!(pasted, copied);
+    }
+
+    #[test]
+    fn other_text_is_not_a_selection() {
+        assert!(MapClipboard::from_json("hello").is_err());
+        assert!(MapClipboard::from_json("{\"format\":\"other\",\"width\":1,\"height\":1,\"layers\":[]}").is_err());
+    }
+
+    #[test]
+    fn selection_must_match_its_size() {
+        let mut copied = clipboard(2, 2);
+        copied.layers[0].tiles.pop();
+        assert!(MapClipboard::from_json(&copied.to_json().unwrap()).is_err());
+        assert!(MapClipboard::from_json(&clipboard(33, 1).to_json().unwrap()).is_err());
+    }
+}
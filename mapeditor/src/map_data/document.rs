@@ -0,0 +1,880 @@
+use graphics::*;
+use indexmap::{IndexMap, IndexSet};
+
+use crate::map_data::*;
+
+// A group of tiles that will be placed on the map, this is either the tileset selection
+// or the tiles picked from the map when they can't be found as is on the tileset
+pub struct TileStamp {
+    pub size: Vec2,
+    pub tiles: Vec<TileData>,
+}
+
+impl TileStamp {
+    pub fn get_tile(&self, x: u32, y: u32) -> TileData {
+        self.tiles[(x + y * self.size.x as u32) as usize]
+    }
+}
+
+// Only the texture id 0 of the first atlas layer is the empty tile, the other atlas layers use that id for a tile
+pub fn is_empty_tile(tiledata: &TileData) -> bool {
+    tiledata.texture_id == 0 && tiledata.texture_layer == 0
+}
+
+pub fn is_same_tile(a: &TileData, b: &TileData) -> bool {
+    a.texture_id == b.texture_id && a.texture_layer == b.texture_layer
+}
+
+// The tiles of a single map, the changed tiles are kept until the GPU map has been updated
+pub struct TileGrid {
+    tiles: Vec<TileData>,
+    changed: Vec<(u32, u32, u32)>,
+}
+
+impl TileGrid {
+    pub fn new() -> Self {
+        Self {
+            tiles: vec![TileData::default(); 8192],
+            changed: Vec::new(),
+        }
+    }
+
+    pub fn get_tile(&self, pos: (u32, u32, u32)) -> TileData {
+        if pos.0 >= 32 || pos.1 >= 32 || pos.2 >= 8 {
+            return TileData::default();
+        }
+        self.tiles[(pos.0 + (pos.1 * 32) + (pos.2 * 1024)) as usize]
+    }
+
+    pub fn set_tile(&mut self, pos: (u32, u32, u32), tile: TileData) {
+        if pos.0 >= 32 || pos.1 >= 32 || pos.2 >= 8 {
+            return;
+        }
+        let tilepos = (pos.0 + (pos.1 * 32) + (pos.2 * 1024)) as usize;
+        if is_same_tile(&self.tiles[tilepos], &tile) {
+            return;
+        }
+        self.tiles[tilepos] = tile;
+        self.changed.push(pos);
+    }
+
+    pub fn clear(&mut self) {
+        (0..8).for_each(|layer| {
+            (0..32).for_each(|x| {
+                (0..32).for_each(|y| {
+                    self.set_tile((x, y, layer), TileData::default());
+                });
+            });
+        });
+    }
+
+    // Returns the position of the tiles that changed since the last call
+    pub fn take_changes(&mut self) -> Vec<(u32, u32, u32)> {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+impl Default for TileGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Side of each map of the document, index 0 is the main map and the others are the linked map strips
+const LINK_SIDES: [(i32, i32); 9] = [
+    (0, 0),
+    (-1, 1), // Top Left
+    (0, 1), // Top
+    (1, 1), // Top Right
+    (-1, 0), // Left
+    (1, 0), // Right
+    (-1, -1), // Bottom Left
+    (0, -1), // Bottom
+    (1, -1), // Bottom Right
+];
+
+// A tile that was painted on a linked map strip, the editor copy this tile on the linked map data
+pub struct LinkedTileChange {
+    pub key: String,
+    // Tile position on the linked map
+    pub pos: (u32, u32, u32),
+    pub tile: TileData,
+}
+
+// The map that is being edited, index 0 of maps is the main map while the other maps are the linked maps
+// Every painting tool work on this model, the map view only show what is on here
+pub struct MapDocument {
+    pub maps: Vec<TileGrid>,
+    // Attributes of the main map
+    pub attributes: Vec<MapAttribute>,
+    changed_attributes: Vec<usize>,
+
+    // Recording, each map keep its own history using the map key
+    records: IndexMap<String, Records>,
+    record_key: String,
+
+    // Key of the map shown on each linked map strip, the strip can only be painted when it has a map
+    link_keys: Vec<Option<String>>,
+    linked_changes: Vec<LinkedTileChange>,
+}
+
+impl MapDocument {
+    pub fn new() -> Self {
+        Self {
+            maps: (0..9).map(|_| TileGrid::new()).collect(),
+            attributes: default_attributes(),
+            changed_attributes: Vec::new(),
+            records: IndexMap::new(),
+            record_key: String::new(),
+            link_keys: vec![None; 9],
+            linked_changes: Vec::new(),
+        }
+    }
+
+    // Get the history of the map that is currently on the main view
+    pub fn record(&mut self) -> &mut Records {
+        self.records.entry(self.record_key.clone()).or_insert_with(Records::new)
+    }
+
+    // This must be called whenever the main view switch to another map
+    // so that the changes will never be recorded or applied on the wrong map
+    pub fn set_record_key(&mut self, key: &str) {
+        if self.record_key == key {
+            return;
+        }
+        self.record().stop_record();
+        self.record_key = key.to_string();
+    }
+
+    // Stop the recording of every map
+    pub fn stop_records(&mut self) {
+        self.records.values_mut().for_each(|record| record.stop_record());
+    }
+
+    // This is set whenever the linked maps are loaded, None is used when there is no map on that side
+    pub fn set_link_key(&mut self, index: usize, key: Option<String>) {
+        self.link_keys[index] = key;
+    }
+
+    // Returns the tiles that were painted on the linked map strips since the last call
+    pub fn take_linked_changes(&mut self) -> Vec<LinkedTileChange> {
+        std::mem::take(&mut self.linked_changes)
+    }
+
+    // The positions relative to the main map that the document has, the main map and the linked map strips around it
+    pub fn is_in_view(x: i32, y: i32) -> bool {
+        (-2..34).contains(&x) && (-2..34).contains(&y)
+    }
+
+    // Find the map that owns a tile position relative to the main map, the linked map strips are the 2 tiles around the main map
+    // Returns the map index, the position on its tile grid and the position on the actual map
+    fn locate_tile(&self, x: i32, y: i32) -> Option<(usize, (u32, u32), (u32, u32))> {
+        if !Self::is_in_view(x, y) {
+            return None;
+        }
+        let side = |pos: i32| if pos < 0 { -1 } else if pos >= 32 { 1 } else { 0 };
+        let (side_x, side_y) = (side(x), side(y));
+        if (side_x, side_y) == (0, 0) {
+            return Some((0, (x as u32, y as u32), (x as u32, y as u32)));
+        }
+        let index = LINK_SIDES.iter().position(|link_side| *link_side == (side_x, side_y))?;
+        self.link_keys[index].as_ref()?;
+
+        let map_pos = ((x - side_x * 32) as u32, (y - side_y * 32) as u32);
+        // The strips of the maps on the left and bottom show the last 2 tiles of these maps
+        let grid_pos = (map_pos.0 - if side_x < 0 { 30 } else { 0 }, map_pos.1 - if side_y < 0 { 30 } else { 0 });
+        Some((index, grid_pos, map_pos))
+    }
+
+    // Get a tile using a position relative to the main map, None is returned when no map can be edited on that position
+    pub fn get_view_tile(&self, x: i32, y: i32, layer: u32) -> Option<TileData> {
+        let (index, grid_pos, _) = self.locate_tile(x, y)?;
+        Some(self.maps[index].get_tile((grid_pos.0, grid_pos.1, layer)))
+    }
+
+    // Paint a tile using a position relative to the main map, the tiles outside the main map are painted on the linked maps
+    pub(crate) fn set_view_tile(&mut self, x: i32, y: i32, layer: u32, tiledata: TileData) {
+        let (index, grid_pos, map_pos) = match self.locate_tile(x, y) {
+            Some(found) => found,
+            None => return,
+        };
+        let last_tile = self.maps[index].get_tile((grid_pos.0, grid_pos.1, layer));
+        // Painting the same tile is not a change, so it must not clear the redo
+        if is_same_tile(&last_tile, &tiledata) {
+            return;
+        }
+        if index == 0 {
+            // Record change for undo purpose
+            self.record().push_change(Vec3::new(x as f32, y as f32, layer as f32), last_tile.texture_id as i32, last_tile.texture_layer);
+        } else {
+            // The change is recorded on the history of the main map, so the stroke is undone on both maps at once
+            let key = self.link_keys[index].clone().unwrap_or_default();
+            self.record().push_linked_change(&key, Vec3::new(map_pos.0 as f32, map_pos.1 as f32, layer as f32), last_tile.texture_id as i32, last_tile.texture_layer);
+            self.linked_changes.push(LinkedTileChange { key, pos: (map_pos.0, map_pos.1, layer), tile: tiledata });
+        }
+        self.maps[index].set_tile((grid_pos.0, grid_pos.1, layer), tiledata);
+    }
+
+    // Paint a tile of a linked map using its position on that map, the tile is not recorded
+    // Returns the tile that was replaced, None is returned when that map is not on a linked map strip
+    fn set_linked_tile(&mut self, key: &str, map_pos: (u32, u32, u32), tiledata: TileData) -> Option<TileData> {
+        let index = self.link_keys.iter().position(|link_key| link_key.as_deref() == Some(key))?;
+        let (side_x, side_y) = LINK_SIDES[index];
+        let (index, grid_pos, _) = self.locate_tile(map_pos.0 as i32 + side_x * 32, map_pos.1 as i32 + side_y * 32)?;
+        let last_tile = self.maps[index].get_tile((grid_pos.0, grid_pos.1, map_pos.2));
+        self.maps[index].set_tile((grid_pos.0, grid_pos.1, map_pos.2), tiledata);
+        self.linked_changes.push(LinkedTileChange { key: key.to_string(), pos: map_pos, tile: tiledata });
+        Some(last_tile)
+    }
+
+    // The part of the brush that is outside the main map is painted on the linked maps
+    pub fn set_tile_group(&mut self, set_pos: Vec2, layer: u32, brush: &TileStamp) {
+        for x in 0..brush.size.x as u32 {
+            for y in 0..brush.size.y as u32 {
+                let tiledata = brush.get_tile(x, y);
+
+                // Make sure we only add tile that are not empty
+                if !is_empty_tile(&tiledata) {
+                    self.set_view_tile(set_pos.x as i32 + x as i32, set_pos.y as i32 + y as i32, layer, tiledata);
+                }
+            }
+        }
+    }
+
+    pub fn delete_tile_group(&mut self, set_pos: Vec2, layer: u32, size: Vec2) {
+        for x in 0..size.x as i32 {
+            for y in 0..size.y as i32 {
+                let (pos_x, pos_y) = (set_pos.x as i32 + x, set_pos.y as i32 + y);
+                if self.get_view_tile(pos_x, pos_y, layer).is_some_and(|last_tile| !is_empty_tile(&last_tile)) {
+                    self.set_view_tile(pos_x, pos_y, layer, TileData::default());
+                }
+            }
+        }
+    }
+
+    // Remove the tiles of the layers within a rectangle, the attributes are only set back to walkable when asked
+    pub fn delete_region(&mut self, set_pos: Vec2, size: Vec2, layers: &[u32], with_attributes: bool) {
+        for layer in layers {
+            self.delete_tile_group(set_pos, *layer, size);
+        }
+        if with_attributes {
+            for x in 0..size.x as u32 {
+                for y in 0..size.y as u32 {
+                    self.delete_attribute(set_pos + Vec2::new(x as f32, y as f32));
+                }
+            }
+        }
+    }
+
+    // Put back everything that was changed on the main map since the record started, the record is kept
+    // so the tools that are redrawn while being dragged still end up as a single undo
+    pub fn revert_current_record(&mut self) {
+        let record = match self.record().current_record() {
+            Some(record) => record,
+            None => return,
+        };
+        let last_tiles: Vec<(u32, u32, u32, TileData)> = record.changes.values().map(|changedata| {
+            (changedata.pos.x as u32, changedata.pos.y as u32, changedata.pos.z as u32, TileData {
+                texture_id: changedata.texture_id as u32,
+                texture_layer: changedata.texture_layer,
+                color: Color::rgba(255, 255, 255, 255),
+            })
+        }).collect();
+        let last_attributes: Vec<(Vec2, MapAttribute)> = record.attributes.values()
+            .map(|attributedata| (attributedata.pos, attributedata.attribute))
+            .collect();
+        let last_linked_tiles: Vec<(String, (u32, u32, u32), TileData)> = record.linked_changes.values().map(|linkeddata| {
+            (linkeddata.key.clone(), (linkeddata.pos.x as u32, linkeddata.pos.y as u32, linkeddata.pos.z as u32), TileData {
+                texture_id: linkeddata.texture_id as u32,
+                texture_layer: linkeddata.texture_layer,
+                color: Color::rgba(255, 255, 255, 255),
+            })
+        }).collect();
+
+        for (x, y, z, tiledata) in last_tiles {
+            self.maps[0].set_tile((x, y, z), tiledata);
+        }
+        for (key, map_pos, tiledata) in last_linked_tiles {
+            self.set_linked_tile(&key, map_pos, tiledata);
+        }
+        for (pos, attribute) in last_attributes {
+            let index = (pos.x + (pos.y * 32.0)) as usize;
+            self.attributes[index] = attribute;
+            self.changed_attributes.push(index);
+        }
+    }
+
+    // Move a rectangle of the main map from origin to new_pos, the move is done again from the original tiles on every call
+    // so a selection that is dragged still ends up as a single undo
+    pub fn move_region(&mut self, resource: &impl TileSource, origin: Vec2, size: Vec2, new_pos: Vec2, layers: &[u32], with_attributes: bool) {
+        self.revert_current_record();
+        if new_pos == origin {
+            return;
+        }
+        let moved = MapClipboard::copy(resource, self, origin, size, layers, with_attributes);
+        self.delete_region(origin, size, layers, with_attributes);
+        moved.paste(resource, self, new_pos);
+    }
+
+    // Get the tiles of a rectangle on the main map, the tiles outside the map are empty
+    pub fn get_tile_group(&self, set_pos: Vec2, layer: u32, size: Vec2) -> TileStamp {
+        let mut tiles = Vec::with_capacity((size.x * size.y) as usize);
+        for y in 0..size.y as i32 {
+            for x in 0..size.x as i32 {
+                let (pos_x, pos_y) = (set_pos.x as i32 + x, set_pos.y as i32 + y);
+                if (0..32).contains(&pos_x) && (0..32).contains(&pos_y) {
+                    tiles.push(self.maps[0].get_tile((pos_x as u32, pos_y as u32, layer)));
+                } else {
+                    tiles.push(TileData::default());
+                }
+            }
+        }
+        TileStamp { size, tiles }
+    }
+
+    // Place every tile of the stamp on the main map including the empty tiles, this is used to paste and move tiles
+    pub fn replace_tile_group(&mut self, set_pos: Vec2, layer: u32, stamp: &TileStamp) {
+        for x in 0..stamp.size.x as u32 {
+            for y in 0..stamp.size.y as u32 {
+                let (pos_x, pos_y) = (set_pos.x as i32 + x as i32, set_pos.y as i32 + y as i32);
+                if !(0..32).contains(&pos_x) || !(0..32).contains(&pos_y) {
+                    continue;
+                }
+                let tiledata = stamp.get_tile(x, y);
+                let last_tile = self.maps[0].get_tile((pos_x as u32, pos_y as u32, layer));
+                if is_same_tile(&last_tile, &tiledata) {
+                    continue;
+                }
+                // Record change for undo purpose
+                self.record().push_change(Vec3::new(pos_x as f32, pos_y as f32, layer as f32), last_tile.texture_id as i32, last_tile.texture_layer);
+                self.maps[0].set_tile((pos_x as u32, pos_y as u32, layer), tiledata);
+            }
+        }
+    }
+
+    // Draw a shape using the brush as a repeating pattern, the pattern starts on the corner of the shape bounds
+    // Everything painted since the record started is put back first, so the shape can be redrawn
+    // while it is being dragged and still end up as a single undo
+    pub fn set_tile_shape(&mut self, shape: ShapeType, start: Vec2, end: Vec2, layer: u32, brush: &TileStamp) {
+        if !self.record().is_recording() {
+            return;
+        }
+        self.revert_current_record();
+
+        let (start, end) = ((start.x as i32, start.y as i32), (end.x as i32, end.y as i32));
+        let corner = (start.0.min(end.0), start.1.min(end.1));
+        for (x, y) in shape_tiles(shape, start, end) {
+            // Shapes are only drawn on the main map
+            if !(0..32).contains(&x) || !(0..32).contains(&y) {
+                continue;
+            }
+            let tiledata = brush.get_tile((x - corner.0).rem_euclid(brush.size.x as i32) as u32,
+                                        (y - corner.1).rem_euclid(brush.size.y as i32) as u32);
+            if !is_empty_tile(&tiledata) {
+                let last_tile = self.maps[0].get_tile((x as u32, y as u32, layer));
+                self.record().push_change(Vec3::new(x as f32, y as f32, layer as f32), last_tile.texture_id as i32, last_tile.texture_layer);
+                self.maps[0].set_tile((x as u32, y as u32, layer), tiledata);
+            }
+        }
+    }
+
+    // The fill continues on the linked map strips, so an area that touches the border also fills the border of the linked map
+    pub fn set_tile_fill(&mut self, set_pos: Vec2, layer: u32, tiledata: TileData) {
+        if is_empty_tile(&tiledata) {
+            return;
+        }
+
+        // Filling the region with its own tile would not change anything
+        let start_pos = (set_pos.x as i32, set_pos.y as i32);
+        let comparedata = match self.get_view_tile(start_pos.0, start_pos.1, layer) {
+            Some(comparedata) => comparedata,
+            None => return,
+        };
+        if is_same_tile(&comparedata, &tiledata) {
+            return;
+        }
+
+        for (x, y) in self.get_fill_region(start_pos, layer) {
+            self.set_view_tile(x, y, layer, tiledata);
+        }
+    }
+
+    // Fill the region with random tiles of the palette, the region can already have tiles of the palette
+    pub fn set_tile_fill_random(&mut self, set_pos: Vec2, layer: u32, palette: &TilePalette, seed: u64) {
+        if palette.is_empty() {
+            return;
+        }
+        for (x, y) in self.get_fill_region((set_pos.x as i32, set_pos.y as i32), layer) {
+            if let Some(tiledata) = palette.pick(seed, x, y, layer) {
+                self.set_view_tile(x, y, layer, tiledata);
+            }
+        }
+    }
+
+    // Paint a random tile of the palette, the part outside the main map is painted on the linked maps
+    pub fn set_tile_random(&mut self, set_pos: Vec2, layer: u32, palette: &TilePalette, seed: u64) {
+        let (x, y) = (set_pos.x as i32, set_pos.y as i32);
+        if let Some(tiledata) = palette.pick(seed, x, y, layer) {
+            if self.get_view_tile(x, y, layer).is_some_and(|last_tile| !is_same_tile(&last_tile, &tiledata)) {
+                self.set_view_tile(x, y, layer, tiledata);
+            }
+        }
+    }
+
+    // Find the tiles that are connected to the start position and have the same texture id and atlas layer as it
+    // The region continues on the linked map strips
+    fn get_fill_region(&self, start_pos: (i32, i32), layer: u32) -> Vec<(i32, i32)> {
+        let comparedata = match self.get_view_tile(start_pos.0, start_pos.1, layer) {
+            Some(comparedata) => comparedata,
+            None => return Vec::new(),
+        };
+
+        // This will hold the location that need to be checked
+        let mut check_pos = vec![start_pos];
+        let mut region = IndexSet::from([start_pos]);
+
+        while let Some((x, y)) = check_pos.pop() {
+            // Check the surrounding tiles, down, up, left and right
+            for (adjust_x, adjust_y) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                let checkpos = (x + adjust_x, y + adjust_y);
+                if region.contains(&checkpos) {
+                    continue;
+                }
+                // We make sure that we only change if they have the same texture id and atlas layer as the starting tile
+                if self.get_view_tile(checkpos.0, checkpos.1, layer).is_some_and(|check_data| is_same_tile(&check_data, &comparedata)) {
+                    region.insert(checkpos);
+                    check_pos.push(checkpos);
+                }
+            }
+        }
+        region.into_iter().collect()
+    }
+
+    // Replace all the attributes of the main map, this is used when loading a map
+    pub fn set_attributes(&mut self, attributes: &[MapAttribute]) {
+        for index in 0..1024 {
+            self.attributes[index] = attributes.get(index).copied().unwrap_or_default();
+        }
+        self.changed_attributes = (0..1024).collect();
+    }
+
+    pub fn set_attribute(&mut self, set_pos: Vec2, attribute: MapAttribute) {
+        // Make sure we wont set attribute outside the map size limit
+        if set_pos.x < 0.0 || set_pos.x >= 32.0 || set_pos.y < 0.0 || set_pos.y >= 32.0 {
+            return;
+        }
+
+        let index = (set_pos.x + (set_pos.y * 32.0)) as usize;
+        if self.attributes[index] == attribute {
+            return;
+        }
+
+        // Record change for undo purpose
+        let last_attribute = self.attributes[index];
+        self.record().push_attribute_change(set_pos, last_attribute);
+
+        self.attributes[index] = attribute;
+        self.changed_attributes.push(index);
+    }
+
+    pub fn delete_attribute(&mut self, set_pos: Vec2) {
+        self.set_attribute(set_pos, MapAttribute::Walkable);
+    }
+
+    pub fn set_attribute_fill(&mut self, set_pos: Vec2, attribute: MapAttribute) {
+        // We will only change the tiles that have the same attribute as the starting tile
+        let comparedata = self.attributes[(set_pos.x + (set_pos.y * 32.0)) as usize];
+        if comparedata == attribute {
+            return;
+        }
+
+        let mut paint_to_map: Vec<Vec2> = Vec::with_capacity(0);
+        paint_to_map.push(set_pos);
+
+        while let Some(pos) = paint_to_map.pop() {
+            self.set_attribute(pos, attribute);
+
+            // Check direction
+            for adjust_pos in [Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0), Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0)] {
+                let checkpos = pos + adjust_pos;
+
+                if checkpos.x >= 0.0 && checkpos.x < 32.0 && checkpos.y >= 0.0 && checkpos.y < 32.0
+                    && self.attributes[(checkpos.x + (checkpos.y * 32.0)) as usize] == comparedata {
+                    paint_to_map.push(checkpos);
+                }
+            }
+        }
+    }
+
+    pub fn get_attribute(&self, pos: Vec2) -> MapAttribute {
+        self.attributes[(pos.x.min(31.0) + (pos.y.min(31.0) * 32.0)) as usize]
+    }
+
+    // Returns the index of the attributes that changed since the last call
+    pub fn take_changed_attributes(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.changed_attributes)
+    }
+
+    // Get the tiles within the area of the main map on a single layer
+    pub fn get_tile_area(&self, start_pos: Vec2, size: Vec2, layer: u32) -> Vec<TileData> {
+        let mut tiles = Vec::with_capacity((size.x * size.y) as usize);
+        for y in 0..size.y as u32 {
+            for x in 0..size.x as u32 {
+                tiles.push(self.maps[0].get_tile((start_pos.x as u32 + x, start_pos.y as u32 + y, layer)));
+            }
+        }
+        tiles
+    }
+
+    pub fn clear_map(&mut self, index: usize) {
+        self.maps[index].clear();
+    }
+
+    // Returns true when there was a change to undo
+    pub fn apply_undo(&mut self) -> bool {
+        if self.record().is_recording() {
+            return false;
+        }
+        let get_change = self.record().get_last_change();
+        if get_change.is_none() {
+            return false;
+        }
+        let data = get_change.unwrap();
+        let inverse = self.apply_record(&data);
+        self.record().push_redo(inverse);
+        true
+    }
+
+    // Returns true when there was a change to redo
+    pub fn apply_redo(&mut self) -> bool {
+        if self.record().is_recording() {
+            return false;
+        }
+        let get_change = self.record().get_last_redo();
+        if get_change.is_none() {
+            return false;
+        }
+        let data = get_change.unwrap();
+        let inverse = self.apply_record(&data);
+        self.record().push_undo(inverse);
+        true
+    }
+
+    // Paint the recorded tiles back on the map and return the inverse of the applied record
+    fn apply_record(&mut self, data: &Record) -> Record {
+        let mut inverse = Record::default();
+        for (key, changedata) in data.changes.iter() {
+            let pos = Vec3::new(changedata.pos.x, changedata.pos.y, changedata.pos.z);
+            let texture_id = changedata.texture_id as u32;
+
+            let last_tile = self.maps[0].get_tile((pos.x as u32, pos.y as u32, pos.z as u32));
+            inverse.changes.insert(key.clone(), ChangeData {
+                pos,
+                texture_id: last_tile.texture_id as i32,
+                texture_layer: last_tile.texture_layer,
+            });
+
+            self.maps[0].set_tile((pos.x as u32, pos.y as u32, pos.z as u32),
+                            TileData {
+                                texture_id,
+                                texture_layer: changedata.texture_layer,
+                                color: Color::rgba(255, 255, 255, 255),
+                            });
+        }
+        for (key, attributedata) in data.attributes.iter() {
+            let index = (attributedata.pos.x + (attributedata.pos.y * 32.0)) as usize;
+            inverse.attributes.insert(key.clone(), AttributeChangeData {
+                pos: attributedata.pos,
+                attribute: self.attributes[index],
+            });
+
+            self.attributes[index] = attributedata.attribute;
+            self.changed_attributes.push(index);
+        }
+        // The linked maps that are no longer next to the main map are skipped
+        for (key, linkeddata) in data.linked_changes.iter() {
+            let tiledata = TileData {
+                texture_id: linkeddata.texture_id as u32,
+                texture_layer: linkeddata.texture_layer,
+                color: Color::rgba(255, 255, 255, 255),
+            };
+            let map_pos = (linkeddata.pos.x as u32, linkeddata.pos.y as u32, linkeddata.pos.z as u32);
+            if let Some(last_tile) = self.set_linked_tile(&linkeddata.key, map_pos, tiledata) {
+                inverse.linked_changes.insert(key.clone(), LinkedChangeData {
+                    key: linkeddata.key.clone(),
+                    pos: linkeddata.pos,
+                    texture_id: last_tile.texture_id as i32,
+                    texture_layer: last_tile.texture_layer,
+                });
+            }
+        }
+        inverse
+    }
+}
+
+impl Default for MapDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    fn brush(size: Vec2, texture_id: u32) -> TileStamp {
+        TileStamp { size, tiles: vec![tile(texture_id); (size.x * size.y) as usize] }
+    }
+
+    // Paint a tile the same way as a single click of the draw tool
+    fn draw(document: &mut MapDocument, pos: Vec2, layer: u32, stamp: &TileStamp) {
+        document.record().set_record();
+        document.set_tile_group(pos, layer, stamp);
+        document.record().stop_record();
+    }
+
+    #[test]
+    fn fill_stops_at_different_tiles() {
+        let mut document = MapDocument::new();
+        // A wall on x = 5 split the map in two areas
+        for y in 0..32 {
+            draw(&mut document, Vec2::new(5.0, y as f32), 0, &brush(Vec2::new(1.0, 1.0), 2));
+        }
+
+        document.record().set_record();
+        document.set_tile_fill(Vec2::new(0.0, 0.0), 0, tile(7));
+        document.record().stop_record();
+
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 7);
+        assert_eq!(document.maps[0].get_tile((4, 31, 0)).texture_id, 7);
+        assert_eq!(document.maps[0].get_tile((5, 10, 0)).texture_id, 2);
+        assert_eq!(document.maps[0].get_tile((6, 10, 0)).texture_id, 0);
+        // Other layers are not touched
+        assert_eq!(document.maps[0].get_tile((0, 0, 1)).texture_id, 0);
+    }
+
+    #[test]
+    fn fill_with_the_same_tile_does_nothing() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 3));
+        document.maps[0].take_changes();
+
+        document.record().set_record();
+        document.set_tile_fill(Vec2::new(0.0, 0.0), 0, tile(3));
+        document.record().stop_record();
+
+        assert!(document.maps[0].take_changes().is_empty());
+        assert_eq!(document.record().data.len(), 1);
+    }
+
+    #[test]
+    fn erase_only_clears_the_area() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(0.0, 0.0), 2, &brush(Vec2::new(4.0, 4.0), 5));
+
+        document.record().set_record();
+        document.delete_tile_group(Vec2::new(1.0, 1.0), 2, Vec2::new(2.0, 2.0));
+        document.record().stop_record();
+
+        assert_eq!(document.maps[0].get_tile((0, 0, 2)).texture_id, 5);
+        assert_eq!(document.maps[0].get_tile((1, 1, 2)).texture_id, 0);
+        assert_eq!(document.maps[0].get_tile((2, 2, 2)).texture_id, 0);
+        assert_eq!(document.maps[0].get_tile((3, 3, 2)).texture_id, 5);
+    }
+
+    #[test]
+    fn tile_with_id_0_on_another_atlas_layer_is_not_empty() {
+        let mut document = MapDocument::new();
+        let tiledata = TileData { texture_id: 0, texture_layer: 1, color: Color::rgba(255, 255, 255, 255) };
+        assert!(!is_empty_tile(&tiledata));
+        draw(&mut document, Vec2::new(2.0, 2.0), 0, &TileStamp { size: Vec2::new(1.0, 1.0), tiles: vec![tiledata] });
+        assert_eq!(document.maps[0].get_tile((2, 2, 0)).texture_layer, 1);
+
+        document.record().set_record();
+        document.set_tile_fill(Vec2::new(0.0, 0.0), 1, tiledata);
+        document.record().stop_record();
+        assert_eq!(document.maps[0].get_tile((31, 31, 1)).texture_layer, 1);
+
+        // The eraser removes it like any other tile
+        document.record().set_record();
+        document.delete_tile_group(Vec2::new(2.0, 2.0), 0, Vec2::new(1.0, 1.0));
+        document.record().stop_record();
+        assert!(is_empty_tile(&document.maps[0].get_tile((2, 2, 0))));
+    }
+
+    #[test]
+    fn draw_outside_the_map_is_clipped() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(30.0, 30.0), 0, &brush(Vec2::new(4.0, 4.0), 1));
+
+        assert_eq!(document.maps[0].get_tile((31, 31, 0)).texture_id, 1);
+        assert_eq!(document.maps[0].take_changes().len(), 4);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_tiles() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(2.0, 2.0), 1));
+        draw(&mut document, Vec2::new(1.0, 1.0), 0, &brush(Vec2::new(1.0, 1.0), 2));
+
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((1, 1, 0)).texture_id, 1);
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((1, 1, 0)).texture_id, 0);
+        assert!(!document.apply_undo());
+
+        assert!(document.apply_redo());
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 1);
+        assert!(document.apply_redo());
+        assert_eq!(document.maps[0].get_tile((1, 1, 0)).texture_id, 2);
+        assert!(!document.apply_redo());
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 1));
+        assert!(document.apply_undo());
+
+        draw(&mut document, Vec2::new(3.0, 3.0), 0, &brush(Vec2::new(1.0, 1.0), 4));
+        assert!(!document.apply_redo());
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 0);
+    }
+
+    #[test]
+    fn painting_the_same_tile_keeps_redo() {
+        let mut document = MapDocument::new();
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 1));
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 2));
+        assert!(document.apply_undo());
+
+        // The tile is already there, nothing is recorded
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 1));
+        assert_eq!(document.record().data.len(), 1);
+        assert!(document.apply_redo());
+        assert_eq!(document.maps[0].get_tile((0, 0, 0)).texture_id, 2);
+    }
+
+    #[test]
+    fn undo_restores_attributes() {
+        let mut document = MapDocument::new();
+        document.record().set_record();
+        document.set_attribute_fill(Vec2::new(0.0, 0.0), MapAttribute::Blocked);
+        document.record().stop_record();
+        assert!(document.attributes.iter().all(|attribute| *attribute == MapAttribute::Blocked));
+
+        assert!(document.apply_undo());
+        assert!(document.attributes.iter().all(|attribute| *attribute == MapAttribute::Walkable));
+    }
+
+    #[test]
+    fn draw_across_the_border_paints_the_linked_map() {
+        let mut document = MapDocument::new();
+        document.set_record_key("0_0_0");
+        document.set_link_key(5, Some("1_0_0".to_string()));
+        draw(&mut document, Vec2::new(31.0, 4.0), 0, &brush(Vec2::new(3.0, 1.0), 6));
+        document.stop_records();
+
+        assert_eq!(document.maps[0].get_tile((31, 4, 0)).texture_id, 6);
+        // The right map strip starts on the first column of that map
+        assert_eq!(document.maps[5].get_tile((0, 4, 0)).texture_id, 6);
+        assert_eq!(document.maps[5].get_tile((1, 4, 0)).texture_id, 6);
+
+        let changes = document.take_linked_changes();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.key == "1_0_0"));
+        assert_eq!(changes[0].pos, (0, 4, 0));
+
+        // The linked map is part of the undo of the main map
+        document.set_record_key("1_0_0");
+        assert!(document.record().data.is_empty());
+    }
+
+    #[test]
+    fn undo_from_the_main_map_reverts_the_linked_map() {
+        let mut document = MapDocument::new();
+        document.set_record_key("0_0_0");
+        document.set_link_key(5, Some("1_0_0".to_string()));
+        draw(&mut document, Vec2::new(1.0, 4.0), 0, &brush(Vec2::new(1.0, 1.0), 2));
+        draw(&mut document, Vec2::new(31.0, 4.0), 0, &brush(Vec2::new(3.0, 1.0), 6));
+        document.take_linked_changes();
+
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((31, 4, 0)).texture_id, 0);
+        assert_eq!(document.maps[5].get_tile((0, 4, 0)).texture_id, 0);
+        assert_eq!(document.maps[5].get_tile((1, 4, 0)).texture_id, 0);
+        // The editor gets the reverted tiles so the linked map data is updated
+        let changes = document.take_linked_changes();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.key == "1_0_0" && change.tile.texture_id == 0));
+        // The stroke before it is still there
+        assert_eq!(document.maps[0].get_tile((1, 4, 0)).texture_id, 2);
+
+        assert!(document.apply_redo());
+        assert_eq!(document.maps[0].get_tile((31, 4, 0)).texture_id, 6);
+        assert_eq!(document.maps[5].get_tile((1, 4, 0)).texture_id, 6);
+        assert_eq!(document.take_linked_changes().len(), 2);
+    }
+
+    #[test]
+    fn fill_continues_on_the_linked_map_strip() {
+        let mut document = MapDocument::new();
+        document.set_link_key(7, Some("0_-1_0".to_string()));
+
+        document.record().set_record();
+        document.set_tile_fill(Vec2::new(0.0, 0.0), 0, tile(8));
+        document.stop_records();
+
+        assert_eq!(document.maps[0].get_tile((31, 31, 0)).texture_id, 8);
+        // The bottom map strip shows the top 2 rows of that map
+        assert_eq!(document.maps[7].get_tile((10, 0, 0)).texture_id, 8);
+        assert_eq!(document.maps[7].get_tile((10, 1, 0)).texture_id, 8);
+        // The row next to the main map is the top row of the bottom map
+        assert_eq!(document.take_linked_changes()[0].pos.1, 31);
+        // The other strips have no map, so they are never painted
+        assert_eq!(document.maps[5].get_tile((0, 0, 0)).texture_id, 0);
+    }
+
+    #[test]
+    fn dragging_a_shape_is_a_single_undo() {
+        let mut document = MapDocument::new();
+        document.record().set_record();
+        // Each mouse move redraw the shape, the previous one must be removed
+        document.set_tile_shape(ShapeType::FilledRectangle, Vec2::new(0.0, 0.0), Vec2::new(5.0, 5.0), 0, &brush(Vec2::new(1.0, 1.0), 3));
+        document.set_tile_shape(ShapeType::FilledRectangle, Vec2::new(0.0, 0.0), Vec2::new(2.0, 1.0), 0, &brush(Vec2::new(1.0, 1.0), 3));
+        document.stop_records();
+
+        assert_eq!(document.maps[0].get_tile((2, 1, 0)).texture_id, 3);
+        assert_eq!(document.maps[0].get_tile((5, 5, 0)).texture_id, 0);
+
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((2, 1, 0)).texture_id, 0);
+        assert!(!document.apply_undo());
+    }
+
+    #[test]
+    fn shape_repeats_the_brush_pattern() {
+        let mut document = MapDocument::new();
+        let pattern = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![tile(1), tile(2)] };
+        document.record().set_record();
+        document.set_tile_shape(ShapeType::Line, Vec2::new(5.0, 3.0), Vec2::new(1.0, 3.0), 0, &pattern);
+        document.stop_records();
+
+        let row: Vec<u32> = (1..6).map(|x| document.maps[0].get_tile((x, 3, 0)).texture_id).collect();
+        assert_eq!(row, vec![1, 2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn each_map_keeps_its_own_history() {
+        let mut document = MapDocument::new();
+        document.set_record_key("0_0_0");
+        draw(&mut document, Vec2::new(0.0, 0.0), 0, &brush(Vec2::new(1.0, 1.0), 1));
+
+        document.set_record_key("1_0_0");
+        assert!(!document.apply_undo());
+
+        document.set_record_key("0_0_0");
+        assert!(document.apply_undo());
+    }
+}
@@ -0,0 +1,517 @@
+use graphics::*;
+use indexmap::IndexMap;
+use log::{error, info, warn};
+
+use crate::map_data::*;
+
+// Resolve the tiles saved on the maps into the tiles used by the map renderer
+pub trait TileSource {
+    // Get the texture id and atlas layer of a tile using its tilesheet name and local tile index
+    fn get_tile_data(&self, name: &str, tile_index: u32) -> Option<TileData>;
+
+    // Find which tilesheet and local tile index owns a texture id on an atlas layer
+    fn get_tile_ref(&self, texture_id: u32, texture_layer: u8) -> Option<(&str, u32)>;
+
+    fn get_map_tile_data(&self, mapdata: &MapData, layer: usize, tile_num: usize) -> Option<TileData> {
+        let (name, tile_index) = mapdata.get_tile_ref(layer, tile_num)?;
+        let tiledata = self.get_tile_data(name, tile_index);
+        if tiledata.is_none() {
+            warn!("Map {}_{}_{} uses missing tile {} of {}", mapdata.x, mapdata.y, mapdata.group, tile_index, name);
+        }
+        tiledata
+    }
+}
+
+#[derive(Debug)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+pub struct EditorData {
+    // Map ID
+    pub x: i32,
+    pub y: i32,
+    pub group: u64,
+
+    // Loaded Maps
+    pub current_index: String,
+    pub maps: IndexMap<String, MapData>,
+    pub did_map_change: IndexMap<String, bool>,
+    // Maps that were upgraded from an older version and has not been reported yet
+    pub migrated_maps: IndexMap<String, bool>,
+    // The unsaved maps have changed since the recovery snapshot was taken
+    pub recovery_outdated: bool,
+}
+
+impl EditorData {
+    pub fn new() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            group: 0,
+            current_index: String::new(),
+            maps: IndexMap::new(),
+            did_map_change: IndexMap::new(),
+            migrated_maps: IndexMap::new(),
+            recovery_outdated: false,
+        }
+    }
+
+    // Make sure the map is on the loaded maps, the current map will not change if the file could not be loaded
+    fn load_map(&mut self, x: i32, y: i32, group: u64) -> Result<(), AscendingError> {
+        let key_data = format!("{}_{}_{}", x, y, group);
+        if !self.maps.contains_key(&key_data) {
+            let (map, version) = load_file(x, y, group)?;
+            self.maps.insert(key_data.clone(), map);
+            // The upgraded map is only written once saved, so it is marked as changed
+            let is_migrated = version < MAP_VERSION;
+            if is_migrated {
+                info!("Map {} was upgraded from version {} to {}", key_data, version, MAP_VERSION);
+                self.migrated_maps.insert(format!("{}  (version {} -> {})", key_data, version, MAP_VERSION), true);
+            }
+            self.did_map_change.insert(key_data, is_migrated);
+        }
+        Ok(())
+    }
+
+    pub fn init_map(&mut self, x: i32, y: i32, group: u64) -> Result<(), AscendingError> {
+        self.load_map(x, y, group)?;
+
+        // Set position and switch the center map
+        self.x = x;
+        self.y = y;
+        self.group = group;
+        self.current_index = format!("{}_{}_{}", x, y, group);
+        Ok(())
+    }
+
+    // This is used when the starting map could not be loaded so that the editor can still be used
+    // The map file will only be replaced once this map is saved, and the old file will be kept as backup
+    pub fn init_default_map(&mut self, x: i32, y: i32, group: u64) {
+        self.x = x;
+        self.y = y;
+        self.group = group;
+        self.current_index = format!("{}_{}_{}", x, y, group);
+        self.maps.insert(self.current_index.clone(), MapData::default(x, y, group));
+        self.did_map_change.insert(self.current_index.clone(), false);
+    }
+
+    pub fn move_map(&mut self, direction: Direction) -> Result<Option<String>, AscendingError> {
+        let (mut x, mut y) = (self.x, self.y);
+        match direction {
+            Direction::East => { x += 1; },
+            Direction::North => { y += 1; },
+            Direction::South => { y -= 1; },
+            Direction::West => { x -= 1; },
+            Direction::NorthEast => { x += 1; y += 1; },
+            Direction::NorthWest => { x -= 1; y += 1; },
+            Direction::SouthEast => { x += 1; y -= 1; },
+            Direction::SouthWest => { x -= 1; y -= 1; },
+        }
+
+        // We load the map first so that we will stay on the current map if it fails
+        self.load_map(x, y, self.group)?;
+        let mut temp_key = None;
+
+        // Check if the current map has changes
+        if let Some(change) = self.did_map_change.get(&self.current_index) {
+            // We will remove the map on the collection when no change has been done
+            if !change {
+                self.did_map_change.remove(&self.current_index);
+                self.maps.remove(&self.current_index);
+            } else {
+                temp_key = Some(self.current_index.clone());
+            }
+        }
+
+        // Switch the center map
+        self.x = x;
+        self.y = y;
+        self.current_index = format!("{}_{}_{}", self.x, self.y, self.group);
+        Ok(temp_key)
+    }
+
+    pub fn save_map_data(&mut self, resource: &impl TileSource, document: &MapDocument, old_map_key: Option<String>) -> Result<(), AscendingError> {
+        // Check if the map should be save as file or temporary data
+        let (should_save, find_key);
+        if old_map_key.is_some() {
+            should_save = false;
+            find_key = old_map_key.unwrap();
+        } else {
+            should_save = true;
+            find_key = self.current_index.clone();
+        }
+        // This handles the copying of data from map tiles to map data
+        if let Some(mapdata) = self.maps.get_mut(&find_key) {
+            // The tilesheet table is rebuilt so that it only list the tilesheets still in use
+            mapdata.tilesheets.clear();
+            (0..8).for_each(|layer| {
+                (0..32).for_each(|x| {
+                    (0..32).for_each(|y| {
+                        let tile_num = get_tile_pos(x, y);
+                        let tiledata = document.maps[0].get_tile((x as u32, y as u32, layer as u32));
+                        match resource.get_tile_ref(tiledata.texture_id, tiledata.texture_layer) {
+                            Some((name, tile_index)) => mapdata.set_tile_ref(layer, tile_num, name, tile_index),
+                            None => mapdata.tile[layer].id[tile_num] = None,
+                        }
+                    });
+                });
+            });
+            mapdata.attribute = document.attributes.clone();
+            if should_save {
+                save_map_file(mapdata)?;
+                remove_recovery_file(mapdata.x, mapdata.y, mapdata.group);
+                // Since we have saved the map, let's mark the map as 'no change'
+                if let Some(did_change) = self.did_map_change.get_mut(&self.current_index) {
+                    *did_change = false;
+                }
+                self.recovery_outdated = true;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save_all_maps(&mut self) -> Result<(), AscendingError> {
+        for (key, value) in self.did_map_change.iter_mut() {
+            // Save only with changes
+            if *value {
+                if let Some(mapdata) = self.maps.get_mut(key) {
+                    save_map_file(mapdata)?;
+                    remove_recovery_file(mapdata.x, mapdata.y, mapdata.group);
+                    *value = false;
+                    self.recovery_outdated = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Copy the unsaved changes of the current map to its map data, the map file is not written
+    fn sync_current_map(&mut self, resource: &impl TileSource, document: &MapDocument) -> Result<(), AscendingError> {
+        if self.did_change(self.x, self.y, self.group) {
+            self.save_map_data(resource, document, Some(self.current_index.clone()))?;
+        }
+        Ok(())
+    }
+
+    // Copy the unsaved changes of the current map and write every unsaved map on the recovery folder
+    pub fn autosave_maps(&mut self, resource: &impl TileSource, document: &MapDocument) -> Result<(), AscendingError> {
+        self.sync_current_map(resource, document)?;
+        self.write_recovery_maps()
+    }
+
+    // Keep a copy of every unsaved map with the changes of the current map, the panic hook writes it if the editor crashes
+    pub fn update_recovery_snapshot(&mut self, resource: &impl TileSource, document: &MapDocument) {
+        if let Err(e) = self.sync_current_map(resource, document) {
+            error!("Failed to copy the current map for recovery, Err {:?}", e);
+            return;
+        }
+        set_recovery_snapshot(self.unsaved_maps());
+        self.recovery_outdated = false;
+    }
+
+    pub fn unsaved_maps(&self) -> Vec<MapData> {
+        self.did_map_change.iter()
+            .filter(|(_, value)| **value)
+            .filter_map(|(key, _)| self.maps.get(key).cloned())
+            .collect()
+    }
+
+    pub fn write_recovery_maps(&self) -> Result<(), AscendingError> {
+        for mapdata in self.unsaved_maps().iter() {
+            write_recovery_file(mapdata)?;
+        }
+        Ok(())
+    }
+
+    // The recovered maps replace the loaded maps and are marked as unsaved
+    pub fn restore_maps(&mut self, recovered_maps: Vec<MapData>) {
+        for mapdata in recovered_maps {
+            let key = format!("{}_{}_{}", mapdata.x, mapdata.y, mapdata.group);
+            self.maps.insert(key.clone(), mapdata);
+            self.did_map_change.insert(key, true);
+        }
+        self.recovery_outdated = true;
+    }
+    
+    pub fn load_map_data(&mut self, resource: &impl TileSource, document: &mut MapDocument) {
+        // Clear the map before we start adding the tiles
+        document.clear_map(0);
+        // Switch to the history of the loaded map
+        document.set_record_key(&self.current_index);
+        // Add the tiles
+        if let Some(mapdata) = self.maps.get(&self.current_index) {
+            (0..8).for_each(|layer| {
+                (0..32).for_each(|x| {
+                    (0..32).for_each(|y| {
+                        let tile_num = get_tile_pos(x, y);
+                        if let Some(tiledata) = resource.get_map_tile_data(mapdata, layer, tile_num) {
+                            document.maps[0].set_tile((x as u32, y as u32, layer as u32), tiledata);
+                        }
+                    });
+                });
+            });
+            document.set_attributes(&mapdata.attribute);
+        }
+    }
+
+    pub fn load_link_maps(&mut self, resource: &impl TileSource, document: &mut MapDocument) {
+        (0..8).for_each(|maplink| {
+            // Clear the map before we start adding the tiles
+            document.clear_map(maplink + 1);
+
+            // Set the map id, position for loading
+            let (start, size, key, x, y);
+            match maplink {
+                1 => { // Top
+                    x = self.x; y = self.y + 1;
+                    size = Vec2::new(32.0, 2.0);
+                    start = Vec2::new(0.0, 0.0);
+                },
+                2 => { // Top Right
+                    x = self.x + 1; y = self.y + 1;
+                    size = Vec2::new(2.0, 2.0);
+                    start = Vec2::new(0.0, 0.0);
+                },
+                3 => { // Left
+                    x = self.x - 1; y = self.y;
+                    size = Vec2::new(2.0, 32.0);
+                    start = Vec2::new(30.0, 0.0);
+                },
+                4 => { // Right
+                    x = self.x + 1; y = self.y;
+                    size = Vec2::new(2.0, 32.0);
+                    start = Vec2::new(0.0, 0.0);
+                },
+                5 => { // Bottom Left
+                    x = self.x - 1; y = self.y - 1;
+                    size = Vec2::new(2.0, 2.0);
+                    start = Vec2::new(30.0, 30.0);
+                },
+                6 => { // Bottom
+                    x = self.x; y = self.y - 1;
+                    size = Vec2::new(32.0, 2.0);
+                    start = Vec2::new(0.0, 30.0);
+                },
+                7 => { // Bottom Right
+                    x = self.x + 1; y = self.y - 1;
+                    size = Vec2::new(2.0, 2.0);
+                    start = Vec2::new(0.0, 30.0);
+                },
+                _ => { // Top Left
+                    x = self.x - 1; y = self.y + 1;
+                    size = Vec2::new(2.0, 2.0);
+                    start = Vec2::new(30.0, 0.0);
+                },
+            }
+            key = format!("{}_{}_{}", x, y, self.group);
+
+            // Let's check if map exist, and only load if map exist
+            // A new map that has not been saved yet only exist on the loaded maps
+            if is_map_exist(x, y, self.group) || self.maps.contains_key(&key) {
+                // Check if map is already on our indexmap, otherwise we load it
+                // The linked map will stay empty if it could not be loaded
+                if let Err(e) = self.load_map(x, y, self.group) {
+                    error!("Failed to load linked map {}, Err {:?}", key, e);
+                }
+
+                // Add the tiles
+                if let Some(mapdata) = self.maps.get(&key) {
+                    (0..8).for_each(|layer| {
+                        (0..size.x as i32).for_each(|x| {
+                            (0..size.y as i32).for_each(|y| {
+                                let tile_num = get_tile_pos(start.x as i32 + x, start.y as i32 + y);
+                                
+                                if let Some(tiledata) = resource.get_map_tile_data(mapdata, layer, tile_num) {
+                                    document.maps[maplink + 1].set_tile((x as u32, y as u32, layer as u32), tiledata);
+                                }
+                            });
+                        });
+                    });
+                }
+            }
+
+            // The linked map strip can only be painted when its map is loaded
+            document.set_link_key(maplink + 1, self.maps.contains_key(&key).then(|| key.clone()));
+        });
+    }
+
+    // Copy the tiles that were painted on the linked map strips to the linked maps, returns true if any map has changed
+    pub fn apply_linked_changes(&mut self, resource: &impl TileSource, document: &mut MapDocument) -> bool {
+        let changes = document.take_linked_changes();
+        for change in changes.iter() {
+            let mapdata = match self.maps.get_mut(&change.key) {
+                Some(mapdata) => mapdata,
+                None => continue,
+            };
+            let (layer, tile_num) = (change.pos.2 as usize, get_tile_pos(change.pos.0 as i32, change.pos.1 as i32));
+            match resource.get_tile_ref(change.tile.texture_id, change.tile.texture_layer) {
+                Some((name, tile_index)) => mapdata.set_tile_ref(layer, tile_num, name, tile_index),
+                None => mapdata.tile[layer].id[tile_num] = None,
+            }
+            if let Some(did_change) = self.did_map_change.get_mut(&change.key) {
+                *did_change = true;
+                self.recovery_outdated = true;
+            }
+        }
+        !changes.is_empty()
+    }
+
+    pub fn get_properties(&self) -> Option<&MapProperties> {
+        self.maps.get(&self.current_index).map(|mapdata| &mapdata.properties)
+    }
+
+    pub fn get_properties_mut(&mut self) -> Option<&mut MapProperties> {
+        self.maps.get_mut(&self.current_index).map(|mapdata| &mut mapdata.properties)
+    }
+
+    pub fn set_map_change(&mut self) -> bool {
+        if let Some(did_change) = self.did_map_change.get_mut(&self.current_index) {
+            *did_change = true;
+            self.recovery_outdated = true;
+            return true;
+        }
+        false
+    }
+
+    pub fn got_changes(&mut self) -> bool {
+        self.did_map_change.values().any(|&value| value)
+    }
+
+    pub fn did_change(&mut self, x: i32, y: i32, group: u64) -> bool {
+        let key_data = format!("{}_{}_{}", x, y, group);
+        if !self.did_map_change.contains_key(&key_data) {
+            return false;
+        }
+        *self.did_map_change.get(&key_data).unwrap()
+    }
+
+    // List the location of the maps on the disk and the maps that are only loaded on the editor
+    pub fn list_maps(&self) -> Vec<(i32, i32, u64)> {
+        let mut locations = list_map_locations().unwrap_or_else(|e| {
+            warn!("Failed to list the maps, Err {:?}", e);
+            Vec::new()
+        });
+        locations.extend(self.maps.keys().filter_map(|key| parse_map_key(key)));
+        locations.sort();
+        locations.dedup();
+        locations
+    }
+
+    // Get a copy of a map without loading it, the loaded map is used first so that its unsaved changes are included
+    pub fn peek_map(&self, x: i32, y: i32, group: u64) -> Result<Option<MapData>, AscendingError> {
+        match self.maps.get(&format!("{}_{}_{}", x, y, group)) {
+            Some(mapdata) => Ok(Some(mapdata.clone())),
+            None => read_map(x, y, group),
+        }
+    }
+}
+
+// A new map is only written once it has content, so visiting a map location never leaves an empty file
+// Returns false when the map was not written
+pub fn save_map_file(mapdata: &MapData) -> Result<bool, AscendingError> {
+    if mapdata.is_empty() && !is_map_exist(mapdata.x, mapdata.y, mapdata.group) {
+        return Ok(false);
+    }
+    mapdata.save_file()?;
+    Ok(true)
+}
+
+pub fn convert_to_dir(dir: usize) -> Direction {
+    match dir {
+        1 => { Direction::North },
+        2 => { Direction::NorthEast },
+        3 => { Direction::West },
+        4 => { Direction::East },
+        5 => { Direction::SouthWest },
+        6 => { Direction::South },
+        7 => { Direction::SouthEast },
+        _ => { Direction::NorthWest },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    // The target map is already loaded so that the tests never touch the map folder
+    fn editor_with_maps(keys: &[(i32, i32)]) -> EditorData {
+        let mut editor_data = EditorData::new();
+        editor_data.init_default_map(0, 0, 0);
+        for (x, y) in keys {
+            let key = format!("{}_{}_0", x, y);
+            editor_data.maps.insert(key.clone(), MapData::default(*x, *y, 0));
+            editor_data.did_map_change.insert(key, false);
+        }
+        editor_data
+    }
+
+    #[test]
+    fn linked_changes_mark_the_linked_map() {
+        let mut editor_data = editor_with_maps(&[(1, 0)]);
+        let mut document = MapDocument::new();
+        document.set_link_key(5, Some("1_0_0".to_string()));
+        document.record().set_record();
+        let brush = TileStamp { size: Vec2::new(1.0, 1.0), tiles: vec![tile(3)] };
+        document.set_tile_group(Vec2::new(32.0, 5.0), 0, &brush);
+        document.stop_records();
+
+        assert!(editor_data.apply_linked_changes(&TestTiles, &mut document));
+        assert_eq!(editor_data.maps["1_0_0"].get_tile_ref(0, get_tile_pos(0, 5)), Some(("tile_0.png", 3)));
+        assert!(editor_data.did_change(1, 0, 0));
+        // The main map was not painted
+        assert!(!editor_data.did_change(0, 0, 0));
+        assert!(!editor_data.apply_linked_changes(&TestTiles, &mut document));
+    }
+
+    #[test]
+    fn recovery_snapshot_has_the_current_map_edits() {
+        let mut editor_data = editor_with_maps(&[(1, 0)]);
+        let mut document = MapDocument::new();
+        document.maps[0].set_tile((2, 3, 0), tile(4));
+        assert!(editor_data.set_map_change());
+        assert!(editor_data.recovery_outdated);
+
+        editor_data.update_recovery_snapshot(&TestTiles, &document);
+        assert!(!editor_data.recovery_outdated);
+        // The unchanged linked map is not part of the recovery
+        let maps = editor_data.unsaved_maps();
+        assert_eq!(maps.len(), 1);
+        assert_eq!(maps[0].get_tile_ref(0, get_tile_pos(2, 3)), Some(("tile_0.png", 4)));
+    }
+
+    #[test]
+    fn move_map_updates_position() {
+        let mut editor_data = editor_with_maps(&[(1, 1)]);
+        assert!(editor_data.move_map(Direction::NorthEast).is_ok());
+        assert_eq!((editor_data.x, editor_data.y), (1, 1));
+        assert_eq!(editor_data.current_index, "1_1_0");
+    }
+
+    #[test]
+    fn move_map_drops_unchanged_map() {
+        let mut editor_data = editor_with_maps(&[(0, -1)]);
+        let temp_key = editor_data.move_map(Direction::South).unwrap();
+        assert!(temp_key.is_none());
+        assert!(!editor_data.maps.contains_key("0_0_0"));
+        assert!(!editor_data.did_map_change.contains_key("0_0_0"));
+    }
+
+    #[test]
+    fn move_map_keeps_changed_map() {
+        let mut editor_data = editor_with_maps(&[(-1, 0)]);
+        assert!(editor_data.set_map_change());
+        let temp_key = editor_data.move_map(Direction::West).unwrap();
+        assert_eq!(temp_key.as_deref(), Some("0_0_0"));
+        assert!(editor_data.maps.contains_key("0_0_0"));
+        assert!(editor_data.did_change(0, 0, 0));
+    }
+}
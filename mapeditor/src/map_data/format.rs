@@ -0,0 +1,219 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::RwLock;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use log::info;
+
+use crate::map_data::*;
+
+pub const PROJECT_PATH: &str = "./data/project.json";
+
+// Binary map header: [magic 4][version 2][compression 1][reserved 1][payload size 4][checksum 4]
+// The version is the map schema version, as the payload can only be read with the layout of that version
+const BINARY_MAGIC: &[u8; 4] = b"AMAP";
+const BINARY_HEADER_SIZE: usize = 16;
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZLIB: u8 = 1;
+// The largest payload a map can have, the size on the header is not trusted before the checksum is checked
+const MAX_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapFormat {
+    #[default]
+    Json,
+    Binary,
+}
+
+impl MapFormat {
+    pub const LIST: [MapFormat; 2] = [MapFormat::Json, MapFormat::Binary];
+
+    pub fn extension<'a>(&self) -> &'a str {
+        match self {
+            MapFormat::Json => "json",
+            MapFormat::Binary => "bin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(MapFormat::Json),
+            "bin" | "binary" => Some(MapFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+// Settings shared by every map of the project, this is saved on ./data/project.json
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub map_format: MapFormat,
+    // The random brush uses this seed on every stroke when it is set, so the same strokes paint the same tiles
+    pub random_seed: Option<u64>,
+}
+
+static PROJECT_CONFIG: RwLock<ProjectConfig> = RwLock::new(ProjectConfig { map_format: MapFormat::Json, random_seed: None });
+
+pub fn project_config() -> ProjectConfig {
+    match PROJECT_CONFIG.read() {
+        Ok(config) => config.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+pub fn map_format() -> MapFormat {
+    project_config().map_format
+}
+
+// Load the project settings, the file is created with the default settings if it does not exist yet
+pub fn load_project_config() -> Result<(), AscendingError> {
+    if !Path::new(PROJECT_PATH).exists() {
+        return save_project_config(ProjectConfig::default());
+    }
+
+    let file = OpenOptions::new().read(true).open(PROJECT_PATH)?;
+    let config: ProjectConfig = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(config) => config,
+        Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to read {}, Err {:?}", PROJECT_PATH, e)))),
+    };
+    set_project_config(config);
+    Ok(())
+}
+
+pub fn save_project_config(config: ProjectConfig) -> Result<(), AscendingError> {
+    let file = OpenOptions::new().write(true).create(true).truncate(true).open(PROJECT_PATH)?;
+    let mut writer = BufWriter::new(file);
+    if let Err(e) = serde_json::to_writer_pretty(&mut writer, &config) {
+        return Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))));
+    }
+    writer.flush()?;
+    set_project_config(config);
+    Ok(())
+}
+
+fn set_project_config(config: ProjectConfig) {
+    match PROJECT_CONFIG.write() {
+        Ok(mut project) => *project = config,
+        Err(e) => *e.into_inner() = config,
+    }
+}
+
+pub fn encode_map(data: &MapData, format: MapFormat) -> Result<Vec<u8>, AscendingError> {
+    match format {
+        MapFormat::Json => serde_json::to_vec_pretty(data)
+            .map_err(|e| AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e)))),
+        MapFormat::Binary => {
+            let payload = match bincode::serialize(data) {
+                Ok(payload) => payload,
+                Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Bincode Error Err {:?}", e)))),
+            };
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&payload)?;
+            let compressed = encoder.finish()?;
+
+            let mut bytes = Vec::with_capacity(BINARY_HEADER_SIZE + compressed.len());
+            bytes.extend_from_slice(BINARY_MAGIC);
+            bytes.extend_from_slice(&(MAP_VERSION as u16).to_le_bytes());
+            bytes.push(COMPRESSION_ZLIB);
+            bytes.push(0);
+            bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+            bytes.extend_from_slice(&compressed);
+            Ok(bytes)
+        }
+    }
+}
+
+// Read the map and upgrade it to the current version, returns the map and the version it was saved with
+pub fn decode_map(bytes: &[u8], format: MapFormat) -> Result<(MapData, u32), AscendingError> {
+    match format {
+        MapFormat::Json => {
+            let mut value: serde_json::Value = match serde_json::from_slice(bytes) {
+                Ok(value) => value,
+                Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e)))),
+            };
+            let version = migrate_map_value(&mut value)?;
+            match serde_json::from_value(value) {
+                Ok(data) => Ok((data, version)),
+                Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e)))),
+            }
+        }
+        MapFormat::Binary => {
+            if bytes.len() < BINARY_HEADER_SIZE || &bytes[0..4] != BINARY_MAGIC {
+                return Err(AscendingError::Other(OtherError::new("Not a binary map file")));
+            }
+            let version = u16::from_le_bytes([bytes[4], bytes[5]]) as u32;
+            if version > MAP_VERSION {
+                return Err(AscendingError::Other(OtherError::new(&format!("Map version {} is newer than the editor (version {})", version, MAP_VERSION))));
+            }
+            let compression = bytes[6];
+            let payload_size = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+            let checksum = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+            if payload_size > MAX_PAYLOAD_SIZE {
+                return Err(AscendingError::Other(OtherError::new(&format!("Binary map payload of {} bytes is too large", payload_size))));
+            }
+
+            let payload = match compression {
+                COMPRESSION_NONE => bytes[BINARY_HEADER_SIZE..].to_vec(),
+                COMPRESSION_ZLIB => {
+                    // One more byte than the header size is read so that a longer payload is still reported as a mismatch
+                    let mut payload = Vec::new();
+                    ZlibDecoder::new(&bytes[BINARY_HEADER_SIZE..]).take(payload_size as u64 + 1).read_to_end(&mut payload)?;
+                    payload
+                }
+                _ => return Err(AscendingError::Other(OtherError::new(&format!("Unknown map compression {}", compression)))),
+            };
+            if payload.len() != payload_size || crc32fast::hash(&payload) != checksum {
+                return Err(AscendingError::Other(OtherError::new("Binary map checksum mismatch")));
+            }
+
+            if version == MAP_VERSION {
+                return match bincode::deserialize(&payload) {
+                    Ok(data) => Ok((data, version)),
+                    Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Bincode Error Err {:?}", e)))),
+                };
+            }
+
+            // Older maps are read with the layout of their version and upgraded like a json map
+            let mut value = match version {
+                1 => match bincode::deserialize::<MapDataV1>(&payload) {
+                    Ok(data) => serde_json::to_value(data),
+                    Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Bincode Error Err {:?}", e)))),
+                },
+                _ => return Err(AscendingError::Other(OtherError::new(&format!("Unsupported binary map version {}", version)))),
+            }.map_err(|e| AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))))?;
+            migrate_map_value(&mut value)?;
+            match serde_json::from_value(value) {
+                Ok(data) => Ok((data, version)),
+                Err(e) => Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e)))),
+            }
+        }
+    }
+}
+
+// Rewrite every map on ./data/maps with the given format, the file of the previous format is kept as backup
+pub fn convert_maps(format: MapFormat) -> Result<usize, AscendingError> {
+    let mut count = 0;
+
+    for (name, source_format) in list_map_files()? {
+        if source_format == format {
+            continue;
+        }
+
+        let (data, _) = match read_map_file(&name, source_format) {
+            Ok(data) => data,
+            Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to convert {}, Err {:?}", name, e)))),
+        };
+
+        write_map_file(&map_file_name(data.x, data.y, data.group, format), &data, format, true)?;
+        remove_map_file(&name)?;
+        info!("Converted map {} to {:?}", name, format);
+        count += 1;
+    }
+
+    Ok(count)
+}
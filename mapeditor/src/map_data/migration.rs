@@ -0,0 +1,155 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::BufReader;
+use std::path::Path;
+use log::{info, warn};
+
+use crate::map_data::*;
+
+// Version of the map schema saved by this editor, this must be increased whenever MapData change
+// and the upgrade from the previous version must be added on MIGRATIONS
+// Version 0: tiles are saved as atlas positions, these are upgraded by migrate_legacy_maps
+// Version 1: tiles are saved as tile references, attributes and properties may be missing
+// Version 2: the map has a version field
+pub const MAP_VERSION: u32 = 2;
+
+// Each entry upgrade the map from version (index + 1) to the next version
+const MIGRATIONS: [fn(&mut Value); (MAP_VERSION - 1) as usize] = [
+    migrate_v1_to_v2,
+];
+
+// Map format of version 1, this is needed for binary maps as they cannot be read without the exact layout
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapDataV1 {
+    pub x: i32,
+    pub y: i32,
+    pub group: u64,
+    pub tilesheets: Vec<String>,
+    pub tile: Vec<Tile>,
+    pub attribute: Vec<MapAttribute>,
+    pub properties: MapProperties,
+}
+
+pub fn get_map_version(value: &Value) -> u32 {
+    match value.get("version").and_then(|version| version.as_u64()) {
+        Some(version) => version as u32,
+        None if value.get("tilesheets").is_some() => 1,
+        None => 0,
+    }
+}
+
+// Upgrade the map to the current version, returns the version the map was saved with
+pub fn migrate_map_value(value: &mut Value) -> Result<u32, AscendingError> {
+    let version = get_map_version(value);
+    if version > MAP_VERSION {
+        return Err(AscendingError::Other(OtherError::new(&format!("Map version {} is newer than the editor (version {})", version, MAP_VERSION))));
+    }
+    if version == 0 {
+        return Err(AscendingError::Other(OtherError::new("Map still use atlas positions and must be migrated with the tilesheets")));
+    }
+
+    for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+        migration(value);
+    }
+    Ok(version)
+}
+
+fn migrate_v1_to_v2(value: &mut Value) {
+    if let Some(map) = value.as_object_mut() {
+        if !map.contains_key("attribute") {
+            map.insert("attribute".to_string(), serde_json::to_value(default_attributes()).unwrap_or_default());
+        }
+        if !map.contains_key("properties") {
+            map.insert("properties".to_string(), serde_json::to_value(MapProperties::default()).unwrap_or_default());
+        }
+        map.insert("version".to_string(), Value::from(2));
+    }
+}
+
+// Map format used before tiles were saved as tile references
+// Each tile id is the raw position of the tile on the texture atlas
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LegacyTile {
+    id: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LegacyMapData {
+    x: i32,
+    y: i32,
+    group: u64,
+    tile: Vec<LegacyTile>,
+}
+
+// This convert every map on ./data/maps that still use the atlas position into tile references
+// The atlas position is resolved with the currently loaded tilesheets, so this must run
+// before any tilesheet is added or reordered. The original file is kept as .legacy
+// get_tile_ref resolve the texture id on the first atlas layer into the tilesheet name and local tile index
+pub fn migrate_legacy_maps<F>(get_tile_ref: F) -> Result<usize, AscendingError>
+where
+    F: Fn(u32) -> Option<(String, u32)>,
+{
+    let mut count = 0;
+
+    for entry in fs::read_dir(MAP_PATH)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+
+        let file = OpenOptions::new().read(true).open(&path)?;
+        let value: Value = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Skipping map migration of {:?}, Err {:?}", path, e);
+                continue;
+            }
+        };
+
+        // Maps that already have a tilesheet table do not need to be migrated
+        if get_map_version(&value) > 0 {
+            continue;
+        }
+
+        let legacy: LegacyMapData = match serde_json::from_value(value) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Skipping map migration of {:?}, Err {:?}", path, e);
+                continue;
+            }
+        };
+
+        let mut mapdata = MapData::default(legacy.x, legacy.y, legacy.group);
+        for (layer, tile) in legacy.tile.iter().enumerate().take(8) {
+            for (tile_num, texture_id) in tile.id.iter().enumerate().take(1024) {
+                if *texture_id == 0 {
+                    continue;
+                }
+                // Legacy maps were always placed on the first atlas layer
+                match get_tile_ref(*texture_id) {
+                    Some((name, tile_index)) => mapdata.set_tile_ref(layer, tile_num, &name, tile_index),
+                    None => warn!("Map {}_{}_{} has unknown texture id {} on layer {}", legacy.x, legacy.y, legacy.group, texture_id, layer),
+                }
+            }
+        }
+
+        // A map file of the same location that is not this file was saved after it, it must not be replaced
+        let target_name = map_file_name(mapdata.x, mapdata.y, mapdata.group, map_format());
+        let other_file = MapFormat::LIST.iter()
+            .map(|format| map_file_name(mapdata.x, mapdata.y, mapdata.group, *format))
+            .find(|name| Path::new(name) != path.as_path() && Path::new(name).exists());
+        if let Some(other_file) = other_file {
+            warn!("Skipping map migration of {:?}, {} already exist", path, other_file);
+            continue;
+        }
+
+        fs::rename(&path, path.with_extension("json.legacy"))?;
+        write_map_file(&target_name, &mapdata, map_format(), true)?;
+        info!("Migrated map {:?}", path);
+        count += 1;
+    }
+
+    Ok(count)
+}
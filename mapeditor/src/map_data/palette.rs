@@ -0,0 +1,127 @@
+use graphics::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::map_data::*;
+
+// The tiles of the tileset selection that the random brush picks from
+pub struct TilePalette {
+    tiles: Vec<(TileData, u32)>,
+    total_weight: u64,
+}
+
+impl TilePalette {
+    // Every tile of the stamp that is not empty, the weights follow the order of the stamp tiles
+    // A tile without a weight has a weight of 1 while a weight of 0 leaves the tile out
+    pub fn new(stamp: &TileStamp, weights: &[u32]) -> Self {
+        let tiles: Vec<(TileData, u32)> = stamp.tiles.iter().enumerate()
+            .map(|(index, tiledata)| (*tiledata, weights.get(index).copied().unwrap_or(1)))
+            .filter(|(tiledata, weight)| !is_empty_tile(tiledata) && *weight > 0)
+            .collect();
+        let total_weight = tiles.iter().map(|(_, weight)| *weight as u64).sum();
+        Self { tiles, total_weight }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    // The tile only depends on the seed and the position, so painting over the same tile in a stroke keeps it
+    pub fn pick(&self, seed: u64, x: i32, y: i32, layer: u32) -> Option<TileData> {
+        if self.total_weight == 0 {
+            return None;
+        }
+        let mut value = position_hash(seed, x, y, layer) % self.total_weight;
+        for (tiledata, weight) in self.tiles.iter() {
+            if value < *weight as u64 {
+                return Some(*tiledata);
+            }
+            value -= *weight as u64;
+        }
+        None
+    }
+}
+
+// SplitMix64 finalizer, a small change of the value changes the whole result
+fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+fn position_hash(seed: u64, x: i32, y: i32, layer: u32) -> u64 {
+    let hash = mix(seed ^ x as u32 as u64);
+    let hash = mix(hash ^ y as u32 as u64);
+    mix(hash ^ layer as u64)
+}
+
+// Seed used by the random brush for a new stroke, the seed of the project makes every stroke reproducible
+pub fn stroke_seed() -> u64 {
+    if let Some(seed) = project_config().random_seed {
+        return seed;
+    }
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    mix(time.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    fn palette(weights: &[u32]) -> TilePalette {
+        let stamp = TileStamp { size: Vec2::new(3.0, 1.0), tiles: vec![tile(1), tile(2), tile(3)] };
+        TilePalette::new(&stamp, weights)
+    }
+
+    #[test]
+    fn same_seed_picks_the_same_tiles() {
+        let picks = |seed: u64| -> Vec<u32> {
+            let palette = palette(&[]);
+            (0..32).map(|x| palette.pick(seed, x, 0, 0).unwrap().texture_id).collect()
+        };
+        assert_eq!(picks(7), picks(7));
+        assert_ne!(picks(7), picks(8));
+    }
+
+    #[test]
+    fn weights_change_how_often_a_tile_is_picked() {
+        let palette = palette(&[0, 9, 1]);
+        let mut counts = [0; 4];
+        for x in 0..32 {
+            for y in 0..32 {
+                counts[palette.pick(1, x, y, 0).unwrap().texture_id as usize] += 1;
+            }
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[2] > counts[3] * 4);
+        assert!(counts[3] > 0);
+    }
+
+    #[test]
+    fn empty_tiles_are_not_part_of_the_palette() {
+        let stamp = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![TileData::default(), TileData::default()] };
+        let palette = TilePalette::new(&stamp, &[]);
+        assert!(palette.is_empty());
+        assert!(palette.pick(0, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn random_fill_stays_within_the_region() {
+        let mut document = MapDocument::new();
+        for y in 0..32 {
+            document.maps[0].set_tile((10, y, 0), tile(9));
+        }
+        document.record().set_record();
+        let stamp = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![TileData::default(), tile(4)] };
+        document.set_tile_fill_random(Vec2::new(0.0, 0.0), 0, &TilePalette::new(&stamp, &[]), 3);
+        // The palette has the tile of the region, the fill must still end
+        let stamp = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![tile(4), tile(1)] };
+        document.set_tile_fill_random(Vec2::new(0.0, 0.0), 0, &TilePalette::new(&stamp, &[]), 3);
+        document.stop_records();
+
+        assert!((0..10).all(|x| [1, 4].contains(&document.maps[0].get_tile((x, 5, 0)).texture_id)));
+        assert_eq!(document.maps[0].get_tile((10, 5, 0)).texture_id, 9);
+        assert_eq!(document.maps[0].get_tile((11, 5, 0)).texture_id, 0);
+    }
+}
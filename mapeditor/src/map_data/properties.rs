@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    None,
+    Rain,
+    Snow,
+    Sandstorm,
+    Thunderstorm,
+}
+
+impl Weather {
+    pub const LIST: [Weather; 5] = [Weather::None, Weather::Rain, Weather::Snow, Weather::Sandstorm, Weather::Thunderstorm];
+
+    pub fn as_str<'a>(&self) -> &'a str {
+        match self {
+            Weather::None => "None",
+            Weather::Rain => "Rain",
+            Weather::Snow => "Snow",
+            Weather::Sandstorm => "Sandstorm",
+            Weather::Thunderstorm => "Thunderstorm",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoneType {
+    #[default]
+    Normal,
+    PvP,
+    SafeZone,
+}
+
+impl ZoneType {
+    pub const LIST: [ZoneType; 3] = [ZoneType::Normal, ZoneType::PvP, ZoneType::SafeZone];
+
+    pub fn as_str<'a>(&self) -> &'a str {
+        match self {
+            ZoneType::Normal => "Normal",
+            ZoneType::PvP => "PvP",
+            ZoneType::SafeZone => "Safe Zone",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RespawnPoint {
+    pub map_x: i32,
+    pub map_y: i32,
+    pub map_group: u64,
+    pub tile_x: u32,
+    pub tile_y: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomProperty {
+    pub key: String,
+    pub value: String,
+}
+
+// Map-level data that is used by the game server, this does not affect how the map is rendered on the editor
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapProperties {
+    pub name: String,
+    pub music: String,
+    pub weather: Weather,
+    // Name of the fog texture, the map has no fog when this is empty
+    pub fog: String,
+    pub fog_opacity: u8,
+    // RGBA
+    pub ambient_color: [u8; 4],
+    pub zone: ZoneType,
+    pub respawn: Option<RespawnPoint>,
+    pub custom: Vec<CustomProperty>,
+}
+
+impl Default for MapProperties {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            music: String::new(),
+            weather: Weather::None,
+            fog: String::new(),
+            fog_opacity: 0,
+            ambient_color: [255, 255, 255, 255],
+            zone: ZoneType::Normal,
+            respawn: None,
+            custom: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use graphics::*;
+use indexmap::IndexMap;
+
+use crate::map_data::MapAttribute;
+
+const MAX_CHANGE: usize = 500;
+
+#[derive(Debug)]
+pub struct ChangeData {
+    pub pos: Vec3,
+    pub texture_id: i32,
+    pub texture_layer: u8,
+}
+
+#[derive(Debug)]
+pub struct AttributeChangeData {
+    pub pos: Vec2,
+    pub attribute: MapAttribute,
+}
+
+// A tile of a linked map that was painted from the main map, pos is the position on the linked map
+#[derive(Debug)]
+pub struct LinkedChangeData {
+    pub key: String,
+    pub pos: Vec3,
+    pub texture_id: i32,
+    pub texture_layer: u8,
+}
+
+#[derive(Default)]
+pub struct Record {
+    pub changes: IndexMap<String, ChangeData>,
+    pub attributes: IndexMap<String, AttributeChangeData>,
+    // The part of the stroke that crossed the seam, so the whole stroke is a single undo
+    pub linked_changes: IndexMap<String, LinkedChangeData>,
+}
+
+impl Record {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.attributes.is_empty() && self.linked_changes.is_empty()
+    }
+}
+
+pub struct Records {
+    in_record: bool,
+    pub data: Vec<Record>,
+    pub redo_data: Vec<Record>,
+    last_index: Option<usize>,
+}
+
+impl Records {
+    pub fn new() -> Self {
+        Self {
+            in_record: false,
+            data: Vec::new(),
+            redo_data: Vec::new(),
+            last_index: None,
+        }
+    }
+
+    pub fn set_record(&mut self) {
+        if self.in_record {
+            return;
+        }
+
+        // Drop the oldest change so that we can keep recording
+        if self.data.len() >= MAX_CHANGE {
+            self.data.remove(0);
+        }
+
+        self.in_record = true;
+        let index = self.data.len();
+        self.last_index = Some(index);
+        self.data.push(Record::default());
+    }
+
+    pub fn push_change(&mut self, pos: Vec3, texture_id: i32, texture_layer: u8) {
+        if !self.in_record {
+            return;
+        }
+
+        if let Some(index) = self.last_index {
+            let key_name = format!("{}_{}_{}", pos.x, pos.y, pos.z);
+            if !self.data[index].changes.contains_key(&key_name) {
+                self.data[index].changes.insert(key_name, ChangeData { pos, texture_id, texture_layer });
+            }
+            // A new change will start a new branch, so we can no longer redo
+            self.redo_data.clear();
+        }
+    }
+
+    pub fn push_attribute_change(&mut self, pos: Vec2, attribute: MapAttribute) {
+        if !self.in_record {
+            return;
+        }
+
+        if let Some(index) = self.last_index {
+            let key_name = format!("{}_{}", pos.x, pos.y);
+            if !self.data[index].attributes.contains_key(&key_name) {
+                self.data[index].attributes.insert(key_name, AttributeChangeData { pos, attribute });
+            }
+            // A new change will start a new branch, so we can no longer redo
+            self.redo_data.clear();
+        }
+    }
+
+    pub fn push_linked_change(&mut self, key: &str, pos: Vec3, texture_id: i32, texture_layer: u8) {
+        if !self.in_record {
+            return;
+        }
+
+        if let Some(index) = self.last_index {
+            let key_name = format!("{}_{}_{}_{}", key, pos.x, pos.y, pos.z);
+            if !self.data[index].linked_changes.contains_key(&key_name) {
+                self.data[index].linked_changes.insert(key_name, LinkedChangeData { key: key.to_string(), pos, texture_id, texture_layer });
+            }
+            // A new change will start a new branch, so we can no longer redo
+            self.redo_data.clear();
+        }
+    }
+
+    pub fn stop_record(&mut self) {
+        if !self.in_record {
+            return;
+        }
+        self.in_record = false;
+        self.last_index = None;
+
+        // We don't need to keep the record if nothing has changed
+        if self.data.last().is_some_and(|record| record.is_empty()) {
+            self.data.pop();
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.in_record
+    }
+
+    // The record that is being filled, None when nothing is being recorded
+    pub fn current_record(&self) -> Option<&Record> {
+        self.data.get(self.last_index?)
+    }
+
+    pub fn get_last_change(&mut self) -> Option<Record> {
+        self.data.pop()
+    }
+
+    pub fn get_last_redo(&mut self) -> Option<Record> {
+        self.redo_data.pop()
+    }
+
+    // This is used by redo to put back the inverse change without clearing the redo branch
+    pub fn push_undo(&mut self, record: Record) {
+        if self.data.len() >= MAX_CHANGE {
+            self.data.remove(0);
+        }
+        self.data.push(record);
+    }
+
+    pub fn push_redo(&mut self, record: Record) {
+        if self.redo_data.len() >= MAX_CHANGE {
+            self.redo_data.remove(0);
+        }
+        self.redo_data.push(record);
+    }
+}
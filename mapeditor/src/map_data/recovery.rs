@@ -0,0 +1,103 @@
+use graphics::*;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, TryLockError};
+use indexmap::IndexMap;
+use log::warn;
+
+use crate::map_data::*;
+
+pub const RECOVERY_PATH: &str = "./data/recovery/";
+// Amount of seconds between each autosave of the unsaved maps
+pub const AUTOSAVE_INTERVAL: f32 = 60.0;
+
+// Copy of the unsaved maps that the panic hook writes on the recovery folder
+static RECOVERY_SNAPSHOT: Mutex<Vec<MapData>> = Mutex::new(Vec::new());
+
+fn recovery_file_name(x: i32, y: i32, group: u64) -> String {
+    format!("{}{}_{}_{}.json", RECOVERY_PATH, x, y, group)
+}
+
+// Write the map on the recovery folder, the actual map file is not touched
+pub fn write_recovery_file(data: &MapData) -> Result<(), AscendingError> {
+    write_map_file(&recovery_file_name(data.x, data.y, data.group), data, MapFormat::Json, false)
+}
+
+pub fn set_recovery_snapshot(maps: Vec<MapData>) {
+    match RECOVERY_SNAPSHOT.lock() {
+        Ok(mut snapshot) => *snapshot = maps,
+        Err(e) => *e.into_inner() = maps,
+    }
+}
+
+// This is used by the panic hook, the lock is not waited on so that a panic while it is held can not block the hook
+pub fn write_recovery_snapshot() -> Result<usize, AscendingError> {
+    let snapshot = match RECOVERY_SNAPSHOT.try_lock() {
+        Ok(snapshot) => snapshot,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return Err(AscendingError::Other(OtherError::new("The recovery maps are being updated"))),
+    };
+    for mapdata in snapshot.iter() {
+        write_recovery_file(mapdata)?;
+    }
+    Ok(snapshot.len())
+}
+
+pub fn remove_recovery_file(x: i32, y: i32, group: u64) {
+    let name = recovery_file_name(x, y, group);
+    if Path::new(&name).exists() {
+        if let Err(e) = fs::remove_file(&name) {
+            warn!("Failed to remove recovery file {}, Err {:?}", name, e);
+        }
+    }
+}
+
+// List the key of every map that was left on the recovery folder
+pub fn get_recovery_list() -> IndexMap<String, bool> {
+    let mut list = IndexMap::new();
+    let entries = match fs::read_dir(RECOVERY_PATH) {
+        Ok(entries) => entries,
+        Err(_) => return list,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        if let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) {
+            list.insert(key.to_string(), true);
+        }
+    }
+    list.sort_keys();
+    list
+}
+
+// Read every recovered map, maps that could not be read are skipped and kept on the folder
+pub fn load_recovery_maps() -> Vec<MapData> {
+    let mut maps = Vec::new();
+    for key in get_recovery_list().keys() {
+        let name = format!("{}{}.json", RECOVERY_PATH, key);
+        let bytes = match fs::read(&name) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to open recovery file {}, Err {:?}", name, e);
+                continue;
+            }
+        };
+        match decode_map(&bytes, MapFormat::Json) {
+            Ok((data, _)) => maps.push(data),
+            Err(e) => warn!("Failed to read recovery file {}, Err {:?}", name, e),
+        }
+    }
+    maps
+}
+
+pub fn clear_recovery_maps() {
+    for key in get_recovery_list().keys() {
+        let name = format!("{}{}.json", RECOVERY_PATH, key);
+        if let Err(e) = fs::remove_file(&name) {
+            warn!("Failed to remove recovery file {}, Err {:?}", name, e);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use graphics::*;
+use image::{imageops, ImageBuffer, Pixel, Rgba, RgbaImage};
+use log::warn;
+
+use crate::map_data::*;
+
+const GRID_COLOR: [u8; 4] = [255, 255, 255, 40];
+const MAP_BORDER_COLOR: [u8; 4] = [255, 255, 255, 140];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderOptions {
+    pub grid: bool,
+    pub attributes: bool,
+}
+
+// Render the maps within the rectangle of the two corners on the CPU, the map with the highest y is on the top
+// The maps that do not exist are left transparent, returns the image and the amount of rendered maps
+pub fn render_maps(group: u64, from: (i32, i32), to: (i32, i32), options: RenderOptions) -> Result<(RgbaImage, usize), AscendingError> {
+    let (min_x, max_x) = (from.0.min(to.0), from.0.max(to.0));
+    let (min_y, max_y) = (from.1.min(to.1), from.1.max(to.1));
+    let map_size = 32 * TILE_SIZE;
+    let width = (max_x - min_x + 1) as u32 * map_size;
+    let height = (max_y - min_y + 1) as u32 * map_size;
+
+    let tilesheets: Vec<(String, u32, RgbaImage)> = load_tilesheet_images()?.into_iter().filter_map(|sheet| {
+        let (sheet_width, sheet_height) = sheet.texture.size();
+        let columns = sheet.columns();
+        let image = ImageBuffer::from_raw(sheet_width, sheet_height, sheet.texture.bytes)?;
+        Some((sheet.name, columns, image))
+    }).collect();
+
+    let layers = layer_draw_order();
+
+    let mut canvas: RgbaImage = ImageBuffer::new(width, height);
+    let mut map_count = 0;
+    for map_y in min_y..=max_y {
+        for map_x in min_x..=max_x {
+            let data = match read_map(map_x, map_y, group)? {
+                Some(data) => data,
+                None => continue,
+            };
+            map_count += 1;
+            let offset_x = (map_x - min_x) as u32 * map_size;
+            let offset_y = (max_y - map_y) as u32 * map_size;
+
+            for layer in layers.iter() {
+                for tile_num in 0..1024 {
+                    let (name, tile_index) = match data.get_tile_ref(*layer as usize, tile_num) {
+                        Some(tile_ref) => tile_ref,
+                        None => continue,
+                    };
+                    let (columns, sheet) = match tilesheets.iter().find(|(sheet_name, _, _)| sheet_name == name) {
+                        Some((_, columns, sheet)) if *columns > 0 => (*columns, sheet),
+                        _ => {
+                            warn!("Map [ X: {} Y: {} Group: {} ] has an unknown tile {} of {}", map_x, map_y, group, tile_index, name);
+                            continue;
+                        }
+                    };
+                    let (src_x, src_y) = ((tile_index % columns) * TILE_SIZE, (tile_index / columns) * TILE_SIZE);
+                    if src_x + TILE_SIZE > sheet.width() || src_y + TILE_SIZE > sheet.height() {
+                        continue;
+                    }
+                    let (pos_x, pos_y) = tile_pixel_pos(offset_x, offset_y, tile_num);
+                    let tile = imageops::crop_imm(sheet, src_x, src_y, TILE_SIZE, TILE_SIZE).to_image();
+                    imageops::overlay(&mut canvas, &tile, pos_x as i64, pos_y as i64);
+                }
+            }
+
+            if options.attributes {
+                for (tile_num, attribute) in data.attribute.iter().enumerate().take(1024) {
+                    let color = attribute.color();
+                    if color.a() == 0 {
+                        continue;
+                    }
+                    let (pos_x, pos_y) = tile_pixel_pos(offset_x, offset_y, tile_num);
+                    let color = [color.r(), color.g(), color.b(), color.a()];
+                    for y in pos_y..pos_y + TILE_SIZE {
+                        for x in pos_x..pos_x + TILE_SIZE {
+                            blend_pixel(&mut canvas, x, y, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if options.grid {
+        for y in 0..height {
+            for x in 0..width {
+                if x % map_size == 0 || y % map_size == 0 {
+                    blend_pixel(&mut canvas, x, y, MAP_BORDER_COLOR);
+                } else if x % TILE_SIZE == 0 || y % TILE_SIZE == 0 {
+                    blend_pixel(&mut canvas, x, y, GRID_COLOR);
+                }
+            }
+        }
+    }
+    Ok((canvas, map_count))
+}
+
+pub fn render_maps_to_png(path: &str, group: u64, from: (i32, i32), to: (i32, i32), options: RenderOptions) -> Result<usize, AscendingError> {
+    let (canvas, map_count) = render_maps(group, from, to, options)?;
+    if map_count == 0 {
+        return Err(AscendingError::Other(OtherError::new(&format!("No map could be found on group {}", group))));
+    }
+    canvas.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(map_count)
+}
+
+// Average color of every tile on the tilesheets, this is used to draw a map with a single pixel per tile
+#[derive(Default)]
+pub struct TileColors {
+    sheets: Vec<(String, Vec<[u8; 4]>)>,
+}
+
+impl TileColors {
+    pub fn load() -> Result<Self, AscendingError> {
+        let mut sheets = Vec::new();
+        for sheet in load_tilesheet_images()? {
+            let (sheet_width, columns) = (sheet.texture.size().0, sheet.columns());
+            let bytes = sheet.texture.bytes();
+            let mut colors = Vec::with_capacity(sheet.tile_count() as usize);
+            for tile_index in 0..sheet.tile_count() {
+                let (start_x, start_y) = ((tile_index % columns) * TILE_SIZE, (tile_index / columns) * TILE_SIZE);
+                // The color is weighted by the alpha so the transparent pixels does not darken the tile
+                let mut total = [0u64; 4];
+                for y in start_y..start_y + TILE_SIZE {
+                    for x in start_x..start_x + TILE_SIZE {
+                        let pos = ((y * sheet_width + x) * 4) as usize;
+                        let alpha = bytes[pos + 3] as u64;
+                        for (channel, value) in total.iter_mut().take(3).enumerate() {
+                            *value += bytes[pos + channel] as u64 * alpha;
+                        }
+                        total[3] += alpha;
+                    }
+                }
+                if total[3] == 0 {
+                    colors.push([0; 4]);
+                } else {
+                    colors.push([(total[0] / total[3]) as u8, (total[1] / total[3]) as u8, (total[2] / total[3]) as u8,
+                        (total[3] / (TILE_SIZE * TILE_SIZE) as u64) as u8]);
+                }
+            }
+            sheets.push((sheet.name, colors));
+        }
+        Ok(Self { sheets })
+    }
+
+    pub fn get(&self, name: &str, tile_index: u32) -> Option<[u8; 4]> {
+        let (_, colors) = self.sheets.iter().find(|(sheet_name, _)| sheet_name == name)?;
+        colors.get(tile_index as usize).copied()
+    }
+}
+
+// Draw the map with a single pixel per tile, the top row of the image is the top row of the map
+pub fn render_thumbnail(data: &MapData, colors: &TileColors) -> RgbaImage {
+    let mut thumbnail: RgbaImage = ImageBuffer::new(32, 32);
+    for layer in layer_draw_order() {
+        for tile_num in 0..1024 {
+            let color = data.get_tile_ref(layer as usize, tile_num)
+                .and_then(|(name, tile_index)| colors.get(name, tile_index));
+            if let Some(color) = color {
+                blend_pixel(&mut thumbnail, tile_num as u32 % 32, 31 - tile_num as u32 / 32, color);
+            }
+        }
+    }
+    thumbnail
+}
+
+// The layer with the highest z is the furthest, so it is drawn first
+fn layer_draw_order() -> Vec<u32> {
+    let mut layers: Vec<u32> = (0..MapLayers::Count as u32).collect();
+    layers.sort_by(|a, b| MapLayers::indexed_layerz(*b).total_cmp(&MapLayers::indexed_layerz(*a)));
+    layers
+}
+
+// The tile y goes up on the map while the image y goes down
+fn tile_pixel_pos(offset_x: u32, offset_y: u32, tile_num: usize) -> (u32, u32) {
+    let (tile_x, tile_y) = (tile_num as u32 % 32, tile_num as u32 / 32);
+    (offset_x + tile_x * TILE_SIZE, offset_y + (31 - tile_y) * TILE_SIZE)
+}
+
+fn blend_pixel(canvas: &mut RgbaImage, x: u32, y: u32, color: [u8; 4]) {
+    canvas.get_pixel_mut(x, y).blend(&Rgba(color));
+}
@@ -0,0 +1,133 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShapeType {
+    Rectangle,
+    FilledRectangle,
+    Line,
+    Ellipse,
+    FilledEllipse,
+}
+
+impl ShapeType {
+    // The shape tool goes through the shapes in this order
+    pub fn next(self) -> Self {
+        match self {
+            ShapeType::Rectangle => ShapeType::FilledRectangle,
+            ShapeType::FilledRectangle => ShapeType::Line,
+            ShapeType::Line => ShapeType::Ellipse,
+            ShapeType::Ellipse => ShapeType::FilledEllipse,
+            ShapeType::FilledEllipse => ShapeType::Rectangle,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShapeType::Rectangle => "Rectangle",
+            ShapeType::FilledRectangle => "Filled Rectangle",
+            ShapeType::Line => "Line",
+            ShapeType::Ellipse => "Ellipse",
+            ShapeType::FilledEllipse => "Filled Ellipse",
+        }
+    }
+}
+
+// Tile positions covered by a shape that is drawn from start to end, both positions are part of the shape
+// The rectangle and ellipse use the two positions as the corners of their bounds
+pub fn shape_tiles(shape: ShapeType, start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    if shape == ShapeType::Line {
+        return line_tiles(start, end);
+    }
+
+    let (min_x, max_x) = (start.0.min(end.0), start.0.max(end.0));
+    let (min_y, max_y) = (start.1.min(end.1), start.1.max(end.1));
+    let inside: Box<dyn Fn(i32, i32) -> bool> = match shape {
+        ShapeType::Ellipse | ShapeType::FilledEllipse => {
+            // The ellipse is checked on the center of each tile so that a small ellipse still looks round
+            let center = ((min_x + max_x + 1) as f32 * 0.5, (min_y + max_y + 1) as f32 * 0.5);
+            let radius = ((max_x - min_x + 1) as f32 * 0.5, (max_y - min_y + 1) as f32 * 0.5);
+            Box::new(move |x, y| {
+                let distance_x = (x as f32 + 0.5 - center.0) / radius.0;
+                let distance_y = (y as f32 + 0.5 - center.1) / radius.1;
+                distance_x * distance_x + distance_y * distance_y <= 1.0
+            })
+        },
+        _ => Box::new(move |x, y| x >= min_x && x <= max_x && y >= min_y && y <= max_y),
+    };
+    let is_filled = matches!(shape, ShapeType::FilledRectangle | ShapeType::FilledEllipse);
+
+    let mut tiles = Vec::new();
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if !inside(x, y) {
+                continue;
+            }
+            // The outline is every tile of the shape that is next to a tile outside the shape
+            let is_edge = !inside(x - 1, y) || !inside(x + 1, y) || !inside(x, y - 1) || !inside(x, y + 1);
+            if is_filled || is_edge {
+                tiles.push((x, y));
+            }
+        }
+    }
+    tiles
+}
+
+// Bresenham's line, every tile is connected to the next one by a side or a corner
+fn line_tiles(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    let (distance_x, distance_y) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+    let (step_x, step_y) = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+    let mut error = distance_x + distance_y;
+    let (mut x, mut y) = start;
+
+    let mut tiles = vec![(x, y)];
+    while (x, y) != end {
+        let double_error = error * 2;
+        if double_error >= distance_y {
+            error += distance_y;
+            x += step_x;
+        }
+        if double_error <= distance_x {
+            error += distance_x;
+            y += step_y;
+        }
+        tiles.push((x, y));
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_outline_skips_the_inside() {
+        let tiles = shape_tiles(ShapeType::Rectangle, (3, 3), (0, 0));
+        assert_eq!(tiles.len(), 12);
+        assert!(!tiles.contains(&(1, 1)));
+        assert_eq!(shape_tiles(ShapeType::FilledRectangle, (0, 0), (3, 3)).len(), 16);
+    }
+
+    #[test]
+    fn line_reaches_both_ends() {
+        let tiles = shape_tiles(ShapeType::Line, (0, 0), (5, 2));
+        assert_eq!(tiles.first(), Some(&(0, 0)));
+        assert_eq!(tiles.last(), Some(&(5, 2)));
+        assert_eq!(tiles.len(), 6);
+    }
+
+    #[test]
+    fn ellipse_stays_within_its_bounds() {
+        let outline = shape_tiles(ShapeType::Ellipse, (0, 0), (6, 4));
+        let filled = shape_tiles(ShapeType::FilledEllipse, (0, 0), (6, 4));
+        assert!(filled.iter().all(|(x, y)| (0..=6).contains(x) && (0..=4).contains(y)));
+        assert!(outline.iter().all(|tile| filled.contains(tile)));
+        // The corners are outside of the ellipse while the center is only part of the filled one
+        assert!(!filled.contains(&(0, 0)));
+        assert!(filled.contains(&(3, 2)) && !outline.contains(&(3, 2)));
+    }
+
+    #[test]
+    fn single_tile_shape() {
+        for shape in [ShapeType::Rectangle, ShapeType::Line, ShapeType::Ellipse] {
+            assert_eq!(shape_tiles(shape, (4, 4), (4, 4)), vec![(4, 4)]);
+        }
+    }
+}
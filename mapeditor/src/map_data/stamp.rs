@@ -0,0 +1,180 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::map_data::*;
+
+pub const STAMP_PATH: &str = "./data/stamps.json";
+
+// A named group of tiles that can be placed with the draw tool, it can have tiles on several layers
+// The tiles are kept by their tilesheet name like the clipboard, so the stamps still work when the atlas changes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryStamp {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<ClipboardLayer>,
+}
+
+impl LibraryStamp {
+    // Capture every layer of a map region, the layers without any tile are left out
+    pub fn from_map(resource: &impl TileSource, document: &MapDocument, name: &str, pos: Vec2, size: Vec2) -> Self {
+        let layers = (0..MapLayers::Count as u32)
+            .map(|layer| (layer, document.get_tile_group(pos, layer, size)))
+            .filter(|(_, stamp)| stamp.tiles.iter().any(|tiledata| !is_empty_tile(tiledata)))
+            .map(|(layer, stamp)| ClipboardLayer::from_tile_stamp(resource, layer, &stamp))
+            .collect();
+        Self { name: name.to_string(), width: size.x as u32, height: size.y as u32, layers }
+    }
+
+    // Capture the tileset selection, the tiles will be placed on the given layer
+    pub fn from_tile_stamp(resource: &impl TileSource, name: &str, layer: u32, stamp: &TileStamp) -> Self {
+        Self {
+            name: name.to_string(),
+            width: stamp.size.x as u32,
+            height: stamp.size.y as u32,
+            layers: vec![ClipboardLayer::from_tile_stamp(resource, layer, stamp)],
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(|layer| layer.tiles.iter().all(|tile| tile.is_none()))
+    }
+
+    // The tiles of the first layer, this is what the random brush and the shapes use
+    pub fn first_layer(&self, resource: &impl TileSource) -> TileStamp {
+        match self.layers.first() {
+            Some(layer) => layer.to_tile_stamp(resource, self.size()),
+            None => TileStamp { size: self.size(), tiles: vec![TileData::default(); (self.width * self.height) as usize] },
+        }
+    }
+
+    // Place the bottom left tile on pos, the empty tiles keep the tiles that are already on the map
+    // The part outside the main map is painted on the linked maps
+    pub fn place(&self, resource: &impl TileSource, document: &mut MapDocument, pos: Vec2) {
+        for layer in self.layers.iter() {
+            document.set_tile_group(pos, layer.layer, &layer.to_tile_stamp(resource, self.size()));
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let tile_count = (self.width * self.height) as usize;
+        (1..=32).contains(&self.width) && (1..=32).contains(&self.height)
+            && self.layers.iter().all(|layer| layer.layer < MapLayers::Count as u32 && layer.tiles.len() == tile_count)
+    }
+}
+
+// The stamps of the project, this is saved on ./data/stamps.json
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StampLibrary {
+    pub stamps: Vec<LibraryStamp>,
+}
+
+impl StampLibrary {
+    // The library is empty when the file does not exist yet
+    pub fn load() -> Result<Self, AscendingError> {
+        if !Path::new(STAMP_PATH).exists() {
+            return Ok(Self::default());
+        }
+
+        let file = OpenOptions::new().read(true).open(STAMP_PATH)?;
+        let library: StampLibrary = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(library) => library,
+            Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to read {}, Err {:?}", STAMP_PATH, e)))),
+        };
+        if let Some(stamp) = library.stamps.iter().find(|stamp| !stamp.is_valid()) {
+            return Err(AscendingError::Other(OtherError::new(&format!("Stamp {} does not match its size", stamp.name))));
+        }
+        Ok(library)
+    }
+
+    // The library is replaced only once it has been completely written, so a failed save keeps the previous stamps
+    pub fn save(&self) -> Result<(), AscendingError> {
+        let bytes = match serde_json::to_vec_pretty(self) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e)))),
+        };
+        write_file_atomic(STAMP_PATH, &bytes, false)
+    }
+
+    // A stamp with the same name is replaced, the stamps are kept sorted by name
+    pub fn add(&mut self, stamp: LibraryStamp) {
+        self.stamps.retain(|saved| saved.name != stamp.name);
+        let index = self.stamps.partition_point(|saved| saved.name.to_lowercase() < stamp.name.to_lowercase());
+        self.stamps.insert(index, stamp);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<LibraryStamp> {
+        (index < self.stamps.len()).then(|| self.stamps.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    #[test]
+    fn map_stamp_keeps_every_layer() {
+        let mut document = MapDocument::new();
+        document.maps[0].set_tile((4, 4, 0), tile(2));
+        document.maps[0].set_tile((5, 5, 3), tile(6));
+
+        let stamp = LibraryStamp::from_map(&TestTiles, &document, "House", Vec2::new(4.0, 4.0), Vec2::new(2.0, 2.0));
+        assert_eq!(stamp.layers.iter().map(|layer| layer.layer).collect::<Vec<u32>>(), vec![0, 3]);
+
+        document.maps[0].set_tile((20, 20, 0), tile(9));
+        document.maps[0].set_tile((21, 20, 0), tile(9));
+        document.record().set_record();
+        stamp.place(&TestTiles, &mut document, Vec2::new(20.0, 20.0));
+        document.stop_records();
+        assert_eq!(document.maps[0].get_tile((20, 20, 0)).texture_id, 2);
+        assert_eq!(document.maps[0].get_tile((21, 21, 3)).texture_id, 6);
+        // The empty tiles of the stamp do not erase the map
+        assert_eq!(document.maps[0].get_tile((21, 20, 0)).texture_id, 9);
+
+        // Both layers are a single undo
+        assert!(document.apply_undo());
+        assert_eq!(document.maps[0].get_tile((20, 20, 0)).texture_id, 9);
+        assert_eq!(document.maps[0].get_tile((21, 21, 3)).texture_id, 0);
+    }
+
+    #[test]
+    fn tileset_stamp_uses_the_given_layer() {
+        let brush = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![tile(1), tile(2)] };
+        let stamp = LibraryStamp::from_tile_stamp(&TestTiles, "Fence", 5, &brush);
+        assert_eq!(stamp.layers[0].layer, 5);
+        assert_eq!(stamp.first_layer(&TestTiles).tiles[1].texture_id, 2);
+        assert!(!stamp.is_empty());
+    }
+
+    #[test]
+    fn same_name_replaces_the_stamp() {
+        let brush = TileStamp { size: Vec2::new(1.0, 1.0), tiles: vec![tile(1)] };
+        let mut library = StampLibrary::default();
+        library.add(LibraryStamp::from_tile_stamp(&TestTiles, "Tree", 0, &brush));
+        library.add(LibraryStamp::from_tile_stamp(&TestTiles, "bush", 0, &brush));
+        library.add(LibraryStamp::from_tile_stamp(&TestTiles, "Tree", 2, &brush));
+
+        assert_eq!(library.stamps.len(), 2);
+        assert_eq!(library.stamps[0].name, "bush");
+        assert_eq!(library.stamps[1].layers[0].layer, 2);
+        assert!(library.remove(2).is_none());
+        assert_eq!(library.remove(0).map(|stamp| stamp.name), Some("bush".to_string()));
+    }
+
+    #[test]
+    fn stamp_must_match_its_size() {
+        let brush = TileStamp { size: Vec2::new(2.0, 1.0), tiles: vec![tile(1), tile(2)] };
+        let mut stamp = LibraryStamp::from_tile_stamp(&TestTiles, "Fence", 0, &brush);
+        assert!(stamp.is_valid());
+        stamp.layers[0].tiles.pop();
+        assert!(!stamp.is_valid());
+    }
+}
@@ -0,0 +1,295 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+use log::warn;
+
+use crate::map_data::*;
+
+pub const TERRAIN_PATH: &str = "./data/terrains.json";
+
+// Neighbour bits of a terrain tile, the north is the tile above which has the higher y
+const NORTH: u8 = 1;
+const NORTH_EAST: u8 = 2;
+const EAST: u8 = 4;
+const SOUTH_EAST: u8 = 8;
+const SOUTH: u8 = 16;
+const SOUTH_WEST: u8 = 32;
+const WEST: u8 = 64;
+const NORTH_WEST: u8 = 128;
+
+const NEIGHBOURS: [(i32, i32, u8); 8] = [
+    (0, 1, NORTH),
+    (1, 1, NORTH_EAST),
+    (1, 0, EAST),
+    (1, -1, SOUTH_EAST),
+    (0, -1, SOUTH),
+    (-1, -1, SOUTH_WEST),
+    (-1, 0, WEST),
+    (-1, 1, NORTH_WEST),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerrainKind {
+    // Uses the 8 neighbours, a corner only counts when both of its sides are part of the terrain
+    Blob47,
+    // Only uses the 4 sides
+    Wang16,
+}
+
+impl TerrainKind {
+    pub fn tile_count(&self) -> usize {
+        match self {
+            TerrainKind::Blob47 => 47,
+            TerrainKind::Wang16 => 16,
+        }
+    }
+}
+
+// A terrain is a group of tiles of a tilesheet, one for each combination of neighbours
+// Blob47 tiles are ordered by their neighbour bits from the lowest to the highest value,
+// Wang16 tiles are ordered by their side bits where north is 1, east 2, south 4 and west 8
+// The terrains are written on ./data/terrains.json, the tiles are the local tile index on the sheet:
+// [{ "name": "Water", "sheet": "tile_0.png", "kind": "Wang16", "tiles": [96, 97, ...] }]
+// See the README for the order of the tiles
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerrainSet {
+    pub name: String,
+    pub sheet: String,
+    pub kind: TerrainKind,
+    pub tiles: Vec<u32>,
+}
+
+impl TerrainSet {
+    pub fn contains(&self, sheet: &str, tile_index: u32) -> bool {
+        self.sheet == sheet && self.tiles.contains(&tile_index)
+    }
+
+    // The tile that fits the neighbour bits
+    pub fn tile_for(&self, mask: u8) -> u32 {
+        let index = match self.kind {
+            TerrainKind::Blob47 => {
+                let mask = reduce_blob_mask(mask);
+                blob_masks().iter().position(|blob_mask| *blob_mask == mask).unwrap_or_default()
+            },
+            TerrainKind::Wang16 => {
+                [NORTH, EAST, SOUTH, WEST].iter().enumerate()
+                    .filter(|(_, side)| mask & **side != 0)
+                    .map(|(bit, _)| 1usize << bit)
+                    .sum()
+            },
+        };
+        self.tiles[index]
+    }
+
+    // Every tile of the terrain must be on its tilesheet, so the terrain can never paint a missing tile
+    fn validate(&self, resource: &impl TileSource) -> Result<(), AscendingError> {
+        if self.tiles.len() != self.kind.tile_count() {
+            return Err(AscendingError::Other(OtherError::new(&format!("Terrain {} needs {} tiles but has {}",
+                self.name, self.kind.tile_count(), self.tiles.len()))));
+        }
+        if let Some(tile_index) = self.tiles.iter().find(|tile_index| resource.get_tile_data(&self.sheet, **tile_index).is_none()) {
+            return Err(AscendingError::Other(OtherError::new(&format!("Terrain {} uses tile {} of {} which does not exist",
+                self.name, tile_index, self.sheet))));
+        }
+        Ok(())
+    }
+}
+
+// Remove the corners that are not next to two sides of the terrain, as these corners do not change the tile
+fn reduce_blob_mask(mask: u8) -> u8 {
+    let mut mask = mask;
+    for (corner, side_a, side_b) in [(NORTH_EAST, NORTH, EAST), (SOUTH_EAST, SOUTH, EAST), (SOUTH_WEST, SOUTH, WEST), (NORTH_WEST, NORTH, WEST)] {
+        if mask & side_a == 0 || mask & side_b == 0 {
+            mask &= !corner;
+        }
+    }
+    mask
+}
+
+// The 47 neighbour bits that are used by a blob terrain, from the lowest to the highest value
+fn blob_masks() -> Vec<u8> {
+    (0..=255u8).filter(|mask| reduce_blob_mask(*mask) == *mask).collect()
+}
+
+// Read the terrains of the project, there is no terrain when the file does not exist
+pub fn load_terrains(resource: &impl TileSource) -> Result<Vec<TerrainSet>, AscendingError> {
+    if !Path::new(TERRAIN_PATH).exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = OpenOptions::new().read(true).open(TERRAIN_PATH)?;
+    let terrains: Vec<TerrainSet> = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(terrains) => terrains,
+        Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to read {}, Err {:?}", TERRAIN_PATH, e)))),
+    };
+    for terrain in terrains.iter() {
+        terrain.validate(resource)?;
+    }
+    Ok(terrains)
+}
+
+// Check if a tile is part of the terrain, None is returned when there is no map that can be edited on that position
+fn is_terrain(document: &MapDocument, resource: &impl TileSource, terrain: &TerrainSet, x: i32, y: i32, layer: u32) -> Option<bool> {
+    let tiledata = document.get_view_tile(x, y, layer)?;
+    Some(resource.get_tile_ref(tiledata.texture_id, tiledata.texture_layer)
+        .is_some_and(|(sheet, tile_index)| terrain.contains(sheet, tile_index)))
+}
+
+// Pick the tile of the terrain that fits the neighbours, the terrain goes on past the edge of the world
+// A tile with a neighbour past the linked map strips is kept as it is, as that neighbour is not known
+fn update_terrain_tile(document: &mut MapDocument, resource: &impl TileSource, terrain: &TerrainSet, x: i32, y: i32, layer: u32) {
+    let mut mask = 0;
+    for (adjust_x, adjust_y, bit) in NEIGHBOURS {
+        let (near_x, near_y) = (x + adjust_x, y + adjust_y);
+        let is_near_terrain = match is_terrain(document, resource, terrain, near_x, near_y, layer) {
+            Some(is_near_terrain) => is_near_terrain,
+            None if MapDocument::is_in_view(near_x, near_y) => true,
+            None => return,
+        };
+        if is_near_terrain {
+            mask |= bit;
+        }
+    }
+    let tile_index = terrain.tile_for(mask);
+    match resource.get_tile_data(&terrain.sheet, tile_index) {
+        Some(tiledata) => {
+            let is_changed = document.get_view_tile(x, y, layer).is_some_and(|last_tile| !is_same_tile(&last_tile, &tiledata));
+            if is_changed {
+                document.set_view_tile(x, y, layer, tiledata);
+            }
+        },
+        None => warn!("Terrain {} uses missing tile {} of {}", terrain.name, tile_index, terrain.sheet),
+    }
+}
+
+// Paint the terrain on a tile using a position relative to the main map, then fix the edges of the terrain around it
+// The neighbours on the linked map strips are updated as well
+pub fn paint_terrain(document: &mut MapDocument, resource: &impl TileSource, terrain: &TerrainSet, set_pos: Vec2, layer: u32) {
+    let (x, y) = (set_pos.x as i32, set_pos.y as i32);
+    match is_terrain(document, resource, terrain, x, y, layer) {
+        // The tile must be part of the terrain before its neighbours are checked
+        Some(false) => {
+            if let Some(tiledata) = resource.get_tile_data(&terrain.sheet, terrain.tile_for(0)) {
+                document.set_view_tile(x, y, layer, tiledata);
+            }
+        },
+        Some(true) => {},
+        None => return,
+    }
+    update_terrain_tile(document, resource, terrain, x, y, layer);
+    for (adjust_x, adjust_y, _) in NEIGHBOURS {
+        if is_terrain(document, resource, terrain, x + adjust_x, y + adjust_y, layer) == Some(true) {
+            update_terrain_tile(document, resource, terrain, x + adjust_x, y + adjust_y, layer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::test_support::*;
+
+    fn wang_terrain() -> TerrainSet {
+        TerrainSet { name: "Water".to_string(), sheet: "tile_0.png".to_string(), kind: TerrainKind::Wang16, tiles: (100..116).collect() }
+    }
+
+    fn tile_index(document: &MapDocument, x: i32, y: i32) -> u32 {
+        document.get_view_tile(x, y, 0).unwrap().texture_id
+    }
+
+    #[test]
+    fn blob_has_47_tiles() {
+        let masks = blob_masks();
+        assert_eq!(masks.len(), 47);
+        assert_eq!(masks.first(), Some(&0));
+        assert_eq!(masks.last(), Some(&255));
+    }
+
+    #[test]
+    fn blob_ignores_lonely_corners() {
+        let terrain = TerrainSet { name: "Grass".to_string(), sheet: "tile_0.png".to_string(), kind: TerrainKind::Blob47, tiles: (0..47).collect() };
+        assert_eq!(terrain.tile_for(NORTH_EAST), terrain.tile_for(0));
+        assert_ne!(terrain.tile_for(NORTH | EAST | NORTH_EAST), terrain.tile_for(NORTH | EAST));
+        assert_eq!(terrain.tile_for(255), 46);
+    }
+
+    #[test]
+    fn terrain_must_use_existing_tiles() {
+        let mut terrain = wang_terrain();
+        assert!(terrain.validate(&TestTiles).is_ok());
+        terrain.tiles.pop();
+        assert!(terrain.validate(&TestTiles).is_err());
+
+        let mut terrain = wang_terrain();
+        terrain.sheet = "tile_9.png".to_string();
+        assert!(terrain.validate(&TestTiles).is_err());
+    }
+
+    #[test]
+    fn painting_updates_the_neighbours() {
+        let mut document = MapDocument::new();
+        let terrain = wang_terrain();
+        document.record().set_record();
+        paint_terrain(&mut document, &TestTiles, &terrain, Vec2::new(5.0, 5.0), 0);
+        // A single tile has no terrain on any side
+        assert_eq!(tile_index(&document, 5, 5), 100);
+
+        paint_terrain(&mut document, &TestTiles, &terrain, Vec2::new(6.0, 5.0), 0);
+        document.stop_records();
+        // The first tile now has the terrain on its east side while the new one has it on its west side
+        assert_eq!(tile_index(&document, 5, 5), 102);
+        assert_eq!(tile_index(&document, 6, 5), 108);
+
+        // Both tiles are a single undo
+        assert!(document.apply_undo());
+        assert_eq!(document.get_view_tile(5, 5, 0).unwrap().texture_id, 0);
+    }
+
+    #[test]
+    fn painting_next_to_the_border_updates_the_linked_map() {
+        let mut document = MapDocument::new();
+        document.set_link_key(5, Some("1_0_0".to_string()));
+        let terrain = wang_terrain();
+        let tiledata = TestTiles.get_tile_data("tile_0.png", 100).unwrap();
+        document.maps[5].set_tile((0, 5, 0), tiledata);
+
+        document.record().set_record();
+        paint_terrain(&mut document, &TestTiles, &terrain, Vec2::new(31.0, 5.0), 0);
+        document.stop_records();
+
+        // The east map gets the terrain on its west side, the tile after it is on the linked map strip as well
+        assert_eq!(tile_index(&document, 32, 5), 108);
+        assert_eq!(document.take_linked_changes().len(), 1);
+    }
+
+    #[test]
+    fn unknown_neighbours_keep_the_strip_tile() {
+        let mut document = MapDocument::new();
+        document.set_link_key(5, Some("1_0_0".to_string()));
+        let terrain = wang_terrain();
+        let tiledata = TestTiles.get_tile_data("tile_0.png", 110).unwrap();
+        document.maps[5].set_tile((1, 5, 0), tiledata);
+
+        document.record().set_record();
+        paint_terrain(&mut document, &TestTiles, &terrain, Vec2::new(32.0, 5.0), 0);
+        document.stop_records();
+
+        // The outer column of the strip has a neighbour that is not on the document, so it is not changed
+        assert_eq!(tile_index(&document, 33, 5), 110);
+        assert_eq!(tile_index(&document, 32, 5), 102);
+    }
+
+    #[test]
+    fn terrain_goes_on_past_the_edge_of_the_world() {
+        let mut document = MapDocument::new();
+        let terrain = wang_terrain();
+        document.record().set_record();
+        paint_terrain(&mut document, &TestTiles, &terrain, Vec2::new(0.0, 5.0), 0);
+        document.stop_records();
+
+        // There is no map on the west side, so the terrain is open on that side
+        assert_eq!(tile_index(&document, 0, 5), 108);
+    }
+}
@@ -0,0 +1,20 @@
+use graphics::*;
+
+use crate::map_data::*;
+
+// The texture id is the local tile index of tile_0.png, the texture id 0 is an empty tile
+pub struct TestTiles;
+
+impl TileSource for TestTiles {
+    fn get_tile_data(&self, name: &str, tile_index: u32) -> Option<TileData> {
+        (name == "tile_0.png" && tile_index > 0).then(|| tile(tile_index))
+    }
+
+    fn get_tile_ref(&self, texture_id: u32, _texture_layer: u8) -> Option<(&str, u32)> {
+        (texture_id > 0).then_some(("tile_0.png", texture_id))
+    }
+}
+
+pub fn tile(texture_id: u32) -> TileData {
+    TileData { texture_id, texture_layer: 0, color: Color::rgba(255, 255, 255, 255) }
+}
@@ -0,0 +1,472 @@
+use graphics::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use log::warn;
+
+use crate::map_data::*;
+
+const TILED_VERSION: &str = "1.10";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiledFormat {
+    Tmj,
+    Tmx,
+}
+
+impl TiledFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()?.to_lowercase().as_str() {
+            "tmj" | "json" => Some(TiledFormat::Tmj),
+            "tmx" => Some(TiledFormat::Tmx),
+            _ => None,
+        }
+    }
+
+    pub fn tileset_extension<'a>(&self) -> &'a str {
+        match self {
+            TiledFormat::Tmj => "tsj",
+            TiledFormat::Tmx => "tsx",
+        }
+    }
+}
+
+// Only the fields used by the editor are kept, the other fields of an imported map are ignored
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledMap {
+    #[serde(rename = "type")]
+    pub map_type: String,
+    pub version: String,
+    pub orientation: String,
+    pub renderorder: String,
+    pub width: u32,
+    pub height: u32,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub infinite: bool,
+    pub nextlayerid: u32,
+    pub nextobjectid: u32,
+    pub layers: Vec<TiledLayer>,
+    pub tilesets: Vec<TiledTilesetRef>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledLayer {
+    #[serde(rename = "type")]
+    pub layer_type: String,
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub opacity: f32,
+    pub visible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    pub data: Vec<u32>,
+}
+
+// The tileset is either an external file on source or embedded with its image
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledTilesetRef {
+    pub firstgid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilewidth: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TiledTileset {
+    #[serde(rename = "type")]
+    pub tileset_type: String,
+    pub version: String,
+    pub name: String,
+    pub image: String,
+    pub imagewidth: u32,
+    pub imageheight: u32,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub columns: u32,
+    pub tilecount: u32,
+    pub margin: u32,
+    pub spacing: u32,
+}
+
+// Export the map with its 8 neighbours of the same group as a single Tiled map, the missing neighbours are left empty
+// The tilesheets are written as external tilesets next to the exported map, returns the amount of exported maps
+pub fn export_tiled(path: &str, x: i32, y: i32, group: u64, include_neighbours: bool) -> Result<usize, AscendingError> {
+    let format = match TiledFormat::from_path(path) {
+        Some(format) => format,
+        None => return Err(AscendingError::Other(OtherError::new(&format!("{} is not a .tmj or .tmx file", path)))),
+    };
+    let folder = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+    if !folder.as_os_str().is_empty() {
+        fs::create_dir_all(&folder)?;
+    }
+
+    let tilesheets = load_tilesheet_images()?;
+    let mut firstgids = Vec::with_capacity(tilesheets.len());
+    let mut tileset_refs = Vec::with_capacity(tilesheets.len());
+    let mut firstgid = 1;
+    for sheet in tilesheets.iter() {
+        let source = format!("{}.{}", sheet_stem(&sheet.name), format.tileset_extension());
+        write_tileset(&folder.join(&source), sheet, &folder, format)?;
+        tileset_refs.push(TiledTilesetRef { firstgid, source: Some(source), ..Default::default() });
+        firstgids.push(firstgid);
+        firstgid += sheet.tile_count();
+    }
+
+    // The main map is on the center, the map above is the map with y + 1 as Tiled starts from the top row
+    let radius = if include_neighbours { 1 } else { 0 };
+    let size = (radius as u32 * 2 + 1) * 32;
+    let mut layers: Vec<TiledLayer> = (0..MapLayers::Count as u32).map(|layer| TiledLayer {
+        layer_type: "tilelayer".to_string(),
+        id: layer + 1,
+        name: MapLayers::as_str(layer).to_string(),
+        x: 0,
+        y: 0,
+        width: size,
+        height: size,
+        opacity: 1.0,
+        visible: true,
+        encoding: None,
+        data: vec![0; (size * size) as usize],
+    }).collect();
+
+    let mut map_count = 0;
+    for map_y in (y - radius)..=(y + radius) {
+        for map_x in (x - radius)..=(x + radius) {
+            let data = match read_map(map_x, map_y, group)? {
+                Some(data) => data,
+                None => continue,
+            };
+            map_count += 1;
+
+            let offset_x = ((map_x - (x - radius)) * 32) as u32;
+            let offset_y = (((y + radius) - map_y) * 32) as u32;
+            for (layer_index, layer) in layers.iter_mut().enumerate() {
+                for tile_num in 0..1024 {
+                    let (name, tile_index) = match data.get_tile_ref(layer_index, tile_num) {
+                        Some(tile_ref) => tile_ref,
+                        None => continue,
+                    };
+                    let gid = match tilesheets.iter().position(|sheet| sheet.name == name) {
+                        Some(sheet) if tile_index < tilesheets[sheet].tile_count() => firstgids[sheet] + tile_index,
+                        _ => {
+                            warn!("Map [ X: {} Y: {} Group: {} ] has an unknown tile {} of {}", map_x, map_y, group, tile_index, name);
+                            continue;
+                        }
+                    };
+                    let (tile_x, tile_y) = (tile_num as u32 % 32, tile_num as u32 / 32);
+                    layer.data[((offset_y + 31 - tile_y) * size + offset_x + tile_x) as usize] = gid;
+                }
+            }
+        }
+    }
+    if map_count == 0 {
+        return Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] could not be found", x, y, group))));
+    }
+
+    let tiled_map = TiledMap {
+        map_type: "map".to_string(),
+        version: TILED_VERSION.to_string(),
+        orientation: "orthogonal".to_string(),
+        renderorder: "right-down".to_string(),
+        width: size,
+        height: size,
+        tilewidth: TILE_SIZE,
+        tileheight: TILE_SIZE,
+        infinite: false,
+        nextlayerid: layers.len() as u32 + 1,
+        nextobjectid: 1,
+        layers,
+        tilesets: tileset_refs,
+    };
+    let content = match format {
+        TiledFormat::Tmj => to_json(&tiled_map)?,
+        TiledFormat::Tmx => map_to_tmx(&tiled_map),
+    };
+    fs::write(path, content)?;
+    Ok(map_count)
+}
+
+fn write_tileset(path: &Path, sheet: &TilesheetImage, folder: &Path, format: TiledFormat) -> Result<(), AscendingError> {
+    let (imagewidth, imageheight) = sheet.texture.size();
+    let tileset = TiledTileset {
+        tileset_type: "tileset".to_string(),
+        version: TILED_VERSION.to_string(),
+        name: sheet_stem(&sheet.name).to_string(),
+        image: relative_path(folder, Path::new(&sheet.path))?,
+        imagewidth,
+        imageheight,
+        tilewidth: TILE_SIZE,
+        tileheight: TILE_SIZE,
+        columns: sheet.columns(),
+        tilecount: sheet.tile_count(),
+        margin: 0,
+        spacing: 0,
+    };
+    let content = match format {
+        TiledFormat::Tmj => to_json(&tileset)?,
+        TiledFormat::Tmx => tileset_to_tsx(&tileset),
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AscendingError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| AscendingError::Other(OtherError::new(&format!("Serdes File Error Err {:?}", e))))
+}
+
+fn map_to_tmx(map: &TiledMap) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<map version=\"{}\" orientation=\"{}\" renderorder=\"{}\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+        map.version, map.orientation, map.renderorder, map.width, map.height, map.tilewidth, map.tileheight, map.nextlayerid, map.nextobjectid));
+    for tileset in map.tilesets.iter() {
+        let source = tileset.source.as_deref().unwrap_or_default();
+        xml.push_str(&format!(" <tileset firstgid=\"{}\" source=\"{}\"/>\n", tileset.firstgid, escape_xml(source)));
+    }
+    for layer in map.layers.iter() {
+        xml.push_str(&format!(" <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\">\n  <data encoding=\"csv\">\n",
+            layer.id, escape_xml(&layer.name), layer.width, layer.height));
+        let rows: Vec<String> = layer.data.chunks(layer.width as usize)
+            .map(|row| row.iter().map(|gid| gid.to_string()).collect::<Vec<String>>().join(","))
+            .collect();
+        xml.push_str(&rows.join(",\n"));
+        xml.push_str("\n</data>\n </layer>\n");
+    }
+    xml.push_str("</map>\n");
+    xml
+}
+
+fn tileset_to_tsx(tileset: &TiledTileset) -> String {
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <tileset version=\"{}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" spacing=\"{}\" margin=\"{}\" tilecount=\"{}\" columns=\"{}\">\n \
+        <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n\
+        </tileset>\n",
+        tileset.version, escape_xml(&tileset.name), tileset.tilewidth, tileset.tileheight, tileset.spacing, tileset.margin,
+        tileset.tilecount, tileset.columns, escape_xml(&tileset.image), tileset.imagewidth, tileset.imageheight)
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn sheet_stem(name: &str) -> &str {
+    Path::new(name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(name)
+}
+
+// Path of the file as seen from the folder, so the exported files can be moved together with the project
+fn relative_path(folder: &Path, file: &Path) -> Result<String, AscendingError> {
+    let folder = fs::canonicalize(if folder.as_os_str().is_empty() { Path::new(".") } else { folder })?;
+    let file = fs::canonicalize(file)?;
+    let folder_parts: Vec<Component> = folder.components().collect();
+    let file_parts: Vec<Component> = file.components().collect();
+    let common = folder_parts.iter().zip(file_parts.iter()).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..folder_parts.len() {
+        path.push("..");
+    }
+    for part in &file_parts[common..] {
+        path.push(part.as_os_str());
+    }
+    Ok(path.to_string_lossy().replace('\\', "/"))
+}
+
+// Tiled keeps the flip and rotation of a tile on the highest bits of the gid
+const TILED_FLIP_FLAGS: u32 = 0xF000_0000;
+
+// The result of an import, the empty parts that were not written are not listed on maps
+// Tiles that could not be placed are listed on unmapped
+pub struct TiledImport {
+    pub maps: Vec<(i32, i32)>,
+    pub unmapped: Vec<String>,
+}
+
+// Our tilesheet that is used by a tileset of the imported map
+struct TilesetMatch {
+    firstgid: u32,
+    name: String,
+    sheet: Option<(String, u32)>,
+}
+
+// Slice a Tiled map into 32x32 maps, the top left part is placed at x and y, the parts on the right on x + 1
+// and the parts below on y - 1. Layers are matched by name and tilesets by the file name of their image
+pub fn import_tiled(path: &str, x: i32, y: i32, group: u64, overwrite: bool) -> Result<TiledImport, AscendingError> {
+    if TiledFormat::from_path(path) != Some(TiledFormat::Tmj) {
+        return Err(AscendingError::Other(OtherError::new(&format!("{} is not a .tmj file", path))));
+    }
+    let tiled_map: TiledMap = match serde_json::from_slice(&fs::read(path)?) {
+        Ok(tiled_map) => tiled_map,
+        Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to read {}, Err {:?}", path, e)))),
+    };
+    if tiled_map.infinite {
+        return Err(AscendingError::Other(OtherError::new("Infinite Tiled maps are not supported")));
+    }
+    if tiled_map.tilewidth != TILE_SIZE || tiled_map.tileheight != TILE_SIZE {
+        return Err(AscendingError::Other(OtherError::new(&format!("Tiled map uses {}x{} tiles instead of {}x{}",
+            tiled_map.tilewidth, tiled_map.tileheight, TILE_SIZE, TILE_SIZE))));
+    }
+
+    let (width, height) = (tiled_map.width.div_ceil(32) as i32, tiled_map.height.div_ceil(32) as i32);
+    if !overwrite {
+        for map_y in 0..height {
+            for map_x in 0..width {
+                if is_map_exist(x + map_x, y - map_y, group) {
+                    return Err(AscendingError::Other(OtherError::new(&format!("Map [ X: {} Y: {} Group: {} ] already exist",
+                        x + map_x, y - map_y, group))));
+                }
+            }
+        }
+    }
+
+    let folder = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+    let tilesheets = load_tilesheet_images()?;
+    let mut unmapped = Vec::new();
+    let mut tilesets = Vec::with_capacity(tiled_map.tilesets.len());
+    for tileset in tiled_map.tilesets.iter() {
+        let tileset_match = match_tileset(tileset, &folder, &tilesheets)?;
+        if tileset_match.sheet.is_none() {
+            unmapped.push(format!("Tileset {} does not use one of the tilesheets of {}", tileset_match.name, TILESHEET_PATH));
+        }
+        tilesets.push(tileset_match);
+    }
+    tilesets.sort_by_key(|tileset| tileset.firstgid);
+
+    let mut maps: Vec<MapData> = Vec::with_capacity((width * height) as usize);
+    for map_y in 0..height {
+        for map_x in 0..width {
+            maps.push(MapData::default(x + map_x, y - map_y, group));
+        }
+    }
+
+    for tiled_layer in tiled_map.layers.iter().filter(|tiled_layer| tiled_layer.layer_type == "tilelayer") {
+        let layer = match (0..MapLayers::Count as u32).find(|layer| MapLayers::as_str(*layer).eq_ignore_ascii_case(&tiled_layer.name)) {
+            Some(layer) => layer as usize,
+            None => {
+                let count = tiled_layer.data.iter().filter(|gid| **gid > 0).count();
+                unmapped.push(format!("Layer {} does not match a map layer, {} tile/s skipped", tiled_layer.name, count));
+                continue;
+            }
+        };
+        if tiled_layer.encoding.as_deref().unwrap_or("csv") != "csv" {
+            unmapped.push(format!("Layer {} is not saved as CSV, the layer is skipped", tiled_layer.name));
+            continue;
+        }
+        // The file could come from anywhere, a layer that does not match its size can not be placed
+        if tiled_layer.width == 0 || tiled_layer.data.len() as u64 != tiled_layer.width as u64 * tiled_layer.height as u64 {
+            unmapped.push(format!("Layer {} has {} tile/s instead of {}x{}, the layer is skipped",
+                tiled_layer.name, tiled_layer.data.len(), tiled_layer.width, tiled_layer.height));
+            continue;
+        }
+
+        let (mut skipped, mut flipped) = (0, 0);
+        for (index, gid) in tiled_layer.data.iter().enumerate() {
+            if *gid == 0 {
+                continue;
+            }
+            if gid & TILED_FLIP_FLAGS != 0 {
+                flipped += 1;
+            }
+            let gid = gid & !TILED_FLIP_FLAGS;
+            let tileset = match tilesets.iter().rev().find(|tileset| tileset.firstgid <= gid) {
+                Some(tileset) => tileset,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let local_id = gid - tileset.firstgid;
+            let sheet_name = match &tileset.sheet {
+                Some((sheet_name, tile_count)) if local_id < *tile_count => sheet_name,
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            let tile_x = index as i32 % tiled_layer.width as i32 + tiled_layer.x;
+            let tile_y = index as i32 / tiled_layer.width as i32 + tiled_layer.y;
+            if tile_x < 0 || tile_y < 0 || tile_x >= width * 32 || tile_y >= height * 32 {
+                skipped += 1;
+                continue;
+            }
+            let map = &mut maps[((tile_y / 32) * width + (tile_x / 32)) as usize];
+            map.set_tile_ref(layer, get_tile_pos(tile_x % 32, 31 - (tile_y % 32)), sheet_name, local_id);
+        }
+        if skipped > 0 {
+            unmapped.push(format!("Layer {} has {} tile/s that do not match one of our tilesheets", tiled_layer.name, skipped));
+        }
+        if flipped > 0 {
+            unmapped.push(format!("Layer {} has {} flipped or rotated tile/s, they are imported without the flip", tiled_layer.name, flipped));
+        }
+    }
+
+    // The parts without any tile are only written when they replace an existing map
+    let mut imported = Vec::with_capacity(maps.len());
+    for map in maps.iter() {
+        if save_map_file(map)? {
+            imported.push((map.x, map.y));
+        }
+    }
+    Ok(TiledImport { maps: imported, unmapped })
+}
+
+// Find the tilesheet with the same file name as the image of the tileset, external tilesets are read from the map folder
+fn match_tileset(tileset: &TiledTilesetRef, folder: &Path, tilesheets: &[TilesheetImage]) -> Result<TilesetMatch, AscendingError> {
+    let (image, columns, tilewidth) = match &tileset.source {
+        Some(source) => {
+            let content = fs::read_to_string(folder.join(source))?;
+            if source.to_lowercase().ends_with(".tsx") {
+                (xml_attribute(&content, "image", "source"),
+                    xml_attribute(&content, "tileset", "columns").and_then(|value| value.parse().ok()),
+                    xml_attribute(&content, "tileset", "tilewidth").and_then(|value| value.parse().ok()))
+            } else {
+                let value: serde_json::Value = match serde_json::from_str(&content) {
+                    Ok(value) => value,
+                    Err(e) => return Err(AscendingError::Other(OtherError::new(&format!("Failed to read {}, Err {:?}", source, e)))),
+                };
+                (value["image"].as_str().map(|image| image.to_string()),
+                    value["columns"].as_u64().map(|columns| columns as u32),
+                    value["tilewidth"].as_u64().map(|tilewidth| tilewidth as u32))
+            }
+        }
+        None => (tileset.image.clone(), tileset.columns, tileset.tilewidth),
+    };
+
+    let image_name = image.as_deref()
+        .and_then(|image| Path::new(image).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = tileset.source.clone().unwrap_or_else(|| image_name.clone());
+
+    // The local tile id only match our tile index when the tileset has the same layout as our tilesheet
+    let sheet = tilesheets.iter()
+        .find(|sheet| sheet.name == image_name
+            && columns.unwrap_or(sheet.columns()) == sheet.columns()
+            && tilewidth.unwrap_or(TILE_SIZE) == TILE_SIZE)
+        .map(|sheet| (sheet.name.clone(), sheet.tile_count()));
+    Ok(TilesetMatch { firstgid: tileset.firstgid, name, sheet })
+}
+
+// Read an attribute of the first element with the given tag, this is only meant for the simple tsx files
+fn xml_attribute(xml: &str, tag: &str, attribute: &str) -> Option<String> {
+    let start = xml.find(&format!("<{} ", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+    let key = format!(" {}=\"", attribute);
+    let value_start = element.find(&key)? + key.len();
+    let value_end = value_start + element[value_start..].find('"')?;
+    Some(element[value_start..value_end].replace("&quot;", "\"").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&"))
+}
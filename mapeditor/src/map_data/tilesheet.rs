@@ -0,0 +1,42 @@
+use graphics::*;
+use std::path::Path;
+
+use crate::map_data::*;
+
+pub const TILESHEET_PATH: &str = "./images/tiles/";
+
+// A tilesheet image read without the GPU, the local tile index goes from left to right then top to bottom
+pub struct TilesheetImage {
+    pub name: String,
+    pub path: String,
+    pub texture: Texture,
+}
+
+impl TilesheetImage {
+    pub fn columns(&self) -> u32 {
+        self.texture.size().0 / TILE_SIZE
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.texture.size().1 / TILE_SIZE
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.columns() * self.rows()
+    }
+}
+
+// Read the tilesheets the same way as the editor, tile_0.png, tile_1.png... until a file is missing
+pub fn load_tilesheet_images() -> Result<Vec<TilesheetImage>, AscendingError> {
+    let mut tilesheets = Vec::new();
+    loop {
+        let name = format!("tile_{}.png", tilesheets.len());
+        let path = format!("{}{}", TILESHEET_PATH, name);
+        if !Path::new(&path).exists() {
+            break;
+        }
+        let texture = Texture::from_file(&path)?;
+        tilesheets.push(TilesheetImage { name, path, texture });
+    }
+    Ok(tilesheets)
+}
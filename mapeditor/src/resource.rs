@@ -1,5 +1,7 @@
 use graphics::*;
+use std::collections::HashMap;
 use crate::collection::TEXTURE_SIZE;
+use crate::map_data::TileSource;
 
 // Modify this based on how many tilesheet image
 pub const MAX_TILESHEET: u32 = 4;
@@ -26,6 +28,8 @@ pub struct TextureAllocation {
     pub white: TextureData,
     pub dialog_button: TextureData,
     pub tilesheet: Vec<TilesheetData>,
+    // (Texture id, atlas layer) -> (tilesheet index, local tile index)
+    tile_lookup: HashMap<(u32, u8), (usize, u32)>,
 }
 
 impl TextureAllocation {
@@ -92,6 +96,16 @@ impl TextureAllocation {
             tilesheet.push(res);
         }
 
+        // Build the reverse lookup so that placed tiles can be saved as stable references
+        let mut tile_lookup = HashMap::new();
+        for (sheet_index, sheet) in tilesheet.iter().enumerate() {
+            for (tile_index, tile) in sheet.tile.tiles.iter().enumerate() {
+                if !tile.is_empty() {
+                    tile_lookup.insert((tile.id, tile.layer as u8), (sheet_index, tile_index as u32));
+                }
+            }
+        }
+
         // Complete! We can now pass the result
         Ok(Self {
             bg_layout,
@@ -105,6 +119,41 @@ impl TextureAllocation {
             white,
             dialog_button,
             tilesheet,
+            tile_lookup,
+        })
+    }
+
+    pub fn get_tilesheet_index(&self, name: &str) -> Option<usize> {
+        self.tilesheet.iter().position(|sheet| sheet.name == name)
+    }
+
+    // Resolve a tilesheet name and its local tile index into the tile data used by the map renderer
+    pub fn get_tile_data(&self, name: &str, tile_index: u32) -> Option<TileData> {
+        let sheet = &self.tilesheet[self.get_tilesheet_index(name)?];
+        let tile = sheet.tile.tiles.get(tile_index as usize)?;
+        if tile.is_empty() {
+            return None;
+        }
+        Some(TileData {
+            texture_id: tile.id,
+            texture_layer: tile.layer as u8,
+            color: Color::rgba(255, 255, 255, 255),
         })
     }
-}
\ No newline at end of file
+
+    // Find which tilesheet and local tile index owns a texture id on an atlas layer
+    pub fn get_tile_ref(&self, texture_id: u32, texture_layer: u8) -> Option<(&str, u32)> {
+        let (sheet_index, tile_index) = self.tile_lookup.get(&(texture_id, texture_layer))?;
+        Some((self.tilesheet[*sheet_index].name.as_str(), *tile_index))
+    }
+}
+
+impl TileSource for TextureAllocation {
+    fn get_tile_data(&self, name: &str, tile_index: u32) -> Option<TileData> {
+        TextureAllocation::get_tile_data(self, name, tile_index)
+    }
+
+    fn get_tile_ref(&self, texture_id: u32, texture_layer: u8) -> Option<(&str, u32)> {
+        TextureAllocation::get_tile_ref(self, texture_id, texture_layer)
+    }
+}
@@ -1,9 +1,13 @@
 use graphics::*;
+use indexmap::IndexMap;
 use crate::resource::*;
 use crate::collection::TEXTURE_SIZE;
+use crate::map_data::{LibraryStamp, TileStamp};
 
 pub const MAX_TILE_X: u32 = 10;
 pub const MAX_TILE_Y: u32 = 20;
+// Highest weight of a tile on the random brush
+pub const MAX_TILE_WEIGHT: u32 = 10;
 
 pub struct Tileset {
     pub map: Map,
@@ -11,6 +15,11 @@ pub struct Tileset {
     pub selection: Image,
     pub select_start: Vec2,
     pub select_size: Vec2,
+    pub stamp: Option<TileStamp>,
+    // Stamp of the library that is placed by the draw tool, this can have tiles on several layers
+    pub library_stamp: Option<LibraryStamp>,
+    // Weights of the random brush, keyed by the tileset index and the tile position on the tileset
+    weights: IndexMap<(usize, u32, u32), u32>,
 }
 
 impl Tileset {
@@ -21,18 +30,21 @@ impl Tileset {
             selection: Image::new(Some(resource.white.allocation), renderer, 1),
             select_start: Vec2::new(0.0, (MAX_TILE_Y - 1) as f32),
             select_size: Vec2::new(1.0, 1.0),
+            stamp: None,
+            library_stamp: None,
+            weights: IndexMap::new(),
         };
 
         // Loop throughout all texture and place them on the map based on their texture location
         for tiledata in &resource.tilesheet[tilesheet.selected_tile].tile.tiles {
-            let (id, x, y) = (tiledata.id, tiledata.x / TEXTURE_SIZE, (MAX_TILE_Y - (tiledata.y / TEXTURE_SIZE) - 1));
+            let (id, layer, x, y) = (tiledata.id, tiledata.layer as u8, tiledata.x / TEXTURE_SIZE, (MAX_TILE_Y - (tiledata.y / TEXTURE_SIZE) - 1));
             // We make sure that we only set those that are not empty tile
-            if id > 0 {
+            if !tiledata.is_empty() {
                 tilesheet.map.set_tile(
                     (x, y, 0),
                     TileData {
                         texture_id: id,
-                        texture_layer: 0,
+                        texture_layer: layer,
                         color: Color::rgba(255, 255, 255, 255),
                     },
                 );
@@ -65,6 +77,8 @@ impl Tileset {
 
         // Set data that will be use when placing tile on map
         self.select_start = start_pos;
+        self.stamp = None;
+        self.library_stamp = None;
         self.select_size = (end_pos - start_pos) + 1.0;
 
         // Adjust selection position and size
@@ -76,6 +90,70 @@ impl Tileset {
         self.select_size
     }
 
+    // Get the tile that will be placed with the draw tool, the position is relative to the selection
+    pub fn get_brush_tile(&self, x: u32, y: u32) -> TileData {
+        if let Some(stamp) = &self.stamp {
+            return stamp.get_tile(x, y);
+        }
+        self.map.get_tile((self.select_start.x as u32 + x, self.select_start.y as u32 + y, 0))
+    }
+
+    pub fn get_brush_size(&self) -> Vec2 {
+        if let Some(stamp) = &self.stamp {
+            return stamp.size;
+        }
+        self.select_size
+    }
+
+    // Get the tiles that will be placed with the draw tool as a stamp
+    pub fn get_brush(&self) -> TileStamp {
+        let size = self.get_brush_size();
+        let mut tiles = Vec::with_capacity((size.x * size.y) as usize);
+        for y in 0..size.y as u32 {
+            for x in 0..size.x as u32 {
+                tiles.push(self.get_brush_tile(x, y));
+            }
+        }
+        TileStamp { size, tiles }
+    }
+
+    // The first layer of the library stamp is kept as the stamp, so the tools that use a single layer still have a brush
+    pub fn use_library_stamp(&mut self, resource: &TextureAllocation, library_stamp: LibraryStamp) {
+        self.stamp = Some(library_stamp.first_layer(resource));
+        self.library_stamp = Some(library_stamp);
+    }
+
+    // A tile has a weight of 1 until it is changed
+    pub fn get_weight(&self, pos: Vec2) -> u32 {
+        self.weights.get(&(self.selected_tile, pos.x as u32, pos.y as u32)).copied().unwrap_or(1)
+    }
+
+    pub fn adjust_weight(&mut self, pos: Vec2, amount: i32) -> u32 {
+        let weight = (self.get_weight(pos) as i32 + amount).clamp(0, MAX_TILE_WEIGHT as i32) as u32;
+        self.weights.insert((self.selected_tile, pos.x as u32, pos.y as u32), weight);
+        weight
+    }
+
+    // The weights of the brush tiles in the same order as get_brush, the tiles picked from the map have no weight
+    pub fn get_brush_weights(&self) -> Vec<u32> {
+        if self.stamp.is_some() {
+            return Vec::new();
+        }
+        let mut weights = Vec::with_capacity((self.select_size.x * self.select_size.y) as usize);
+        for y in 0..self.select_size.y as u32 {
+            for x in 0..self.select_size.x as u32 {
+                weights.push(self.get_weight(self.select_start + Vec2::new(x as f32, y as f32)));
+            }
+        }
+        weights
+    }
+
+    // Returns the position of a tile on the tileset using its local index on the tilesheet
+    pub fn get_tile_pos(resource: &TextureAllocation, tileset_index: usize, tile_index: u32) -> Option<Vec2> {
+        let tiledata = resource.tilesheet.get(tileset_index)?.tile.tiles.get(tile_index as usize)?;
+        Some(Vec2::new((tiledata.x / TEXTURE_SIZE) as f32, (MAX_TILE_Y - (tiledata.y / TEXTURE_SIZE) - 1) as f32))
+    }
+
     pub fn change_tileset(&mut self, resource: &TextureAllocation, tileset_index: usize) {
         if self.selected_tile == tileset_index {
             return;
@@ -94,14 +172,14 @@ impl Tileset {
 
         // Loop throughout all texture and place them on the map based on their texture location
         for tiledata in &resource.tilesheet[tileset_index].tile.tiles {
-            let (id, x, y) = (tiledata.id, tiledata.x / TEXTURE_SIZE, (MAX_TILE_Y - (tiledata.y / TEXTURE_SIZE) - 1));
+            let (id, layer, x, y) = (tiledata.id, tiledata.layer as u8, tiledata.x / TEXTURE_SIZE, (MAX_TILE_Y - (tiledata.y / TEXTURE_SIZE) - 1));
             // We make sure that we only set those that are not empty tile
-            if id > 0 {
+            if !tiledata.is_empty() {
                 self.map.set_tile(
                     (x, y, 0),
                     TileData {
                         texture_id: id,
-                        texture_layer: 0,
+                        texture_layer: layer,
                         color: Color::rgba(255, 255, 255, 255),
                     },
                 );